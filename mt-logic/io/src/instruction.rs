@@ -3,6 +3,9 @@ use gstd::{msg, ActorId};
 use mt_storage_io::{MTStorageAction, MTStorageEvent};
 use primitive_types::H256;
 
+/// Step number reserved for compensations.
+const COMPENSATION_STEP: u32 = u32::MAX;
+
 #[derive(Debug, Encode, Decode, TypeInfo, Clone)]
 pub enum InstructionState {
     ScheduledRun,
@@ -88,10 +91,66 @@ impl Instruction {
     }
 }
 
+/// Result of [`execute_instructions`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum ExecutionResult {
+    /// All instructions are executed.
+    Success,
+    /// An instruction failed and all executed ones are compensated.
+    Aborted,
+    /// An instruction failed and compensation failed too, so the execution must be retried.
+    AbortFailed,
+}
+
+/// Executes `instructions` in order, compensating the executed ones if any of them fails.
+///
+/// Instructions keep their state, so a retried execution doesn't repeat finished steps.
+pub async fn execute_instructions(instructions: &mut [Instruction]) -> ExecutionResult {
+    let mut failed_step = None;
+    for (step, instruction) in instructions.iter_mut().enumerate() {
+        if instruction.start().await.is_err() {
+            failed_step = Some(step);
+            break;
+        }
+    }
+
+    let failed_step = match failed_step {
+        Some(step) => step,
+        None => return ExecutionResult::Success,
+    };
+
+    match abort_instructions(&mut instructions[..failed_step]).await {
+        Ok(()) => ExecutionResult::Aborted,
+        Err(()) => ExecutionResult::AbortFailed,
+    }
+}
+
+/// Compensates executed `instructions` in reverse order.
+pub async fn abort_instructions(instructions: &mut [Instruction]) -> Result<(), ()> {
+    for instruction in instructions.iter_mut().rev() {
+        instruction.abort().await?;
+    }
+
+    Ok(())
+}
+
+/// Derives a hash of `step` of the transaction with `transaction_hash`.
+///
+/// Storages execute an operation with the same hash only once,
+/// so every operation of a transaction must have its own hash.
+pub fn get_step_hash(transaction_hash: H256, step: u32) -> H256 {
+    if step == 0 {
+        return transaction_hash;
+    }
+
+    sp_core_hashing::blake2_256(&[transaction_hash.as_bytes(), &step.to_be_bytes()].concat()).into()
+}
+
 pub fn create_decrease_instruction(
     transaction_hash: H256,
     sender_storage: &ActorId,
     token_id: u128,
+    snapshot_id: u64,
     msg_source: &ActorId,
     account: &ActorId,
     amount: u128,
@@ -101,13 +160,15 @@ pub fn create_decrease_instruction(
         MTStorageAction::DecreaseBalance {
             transaction_hash,
             token_id,
+            snapshot_id,
             msg_source: *msg_source,
             account: *account,
             amount,
         },
         Some(MTStorageAction::IncreaseBalance {
-            transaction_hash,
+            transaction_hash: get_step_hash(transaction_hash, COMPENSATION_STEP),
             token_id,
+            snapshot_id,
             account: *account,
             amount,
         }),
@@ -118,6 +179,7 @@ pub fn create_increase_instruction(
     transaction_hash: H256,
     recipient_storage: &ActorId,
     token_id: u128,
+    snapshot_id: u64,
     account: &ActorId,
     amount: u128,
 ) -> Instruction {
@@ -126,9 +188,44 @@ pub fn create_increase_instruction(
         MTStorageAction::IncreaseBalance {
             transaction_hash,
             token_id,
+            snapshot_id,
             account: *account,
             amount,
         },
-        None,
+        Some(MTStorageAction::DecreaseBalance {
+            transaction_hash: get_step_hash(transaction_hash, COMPENSATION_STEP),
+            token_id,
+            snapshot_id,
+            msg_source: *account,
+            account: *account,
+            amount,
+        }),
+    )
+}
+
+/// Creates an instruction which sets the approval of `account` by `owner`
+/// and restores `previous_approval` on compensation.
+pub fn create_approve_instruction(
+    transaction_hash: H256,
+    owner_storage: &ActorId,
+    owner: &ActorId,
+    account: &ActorId,
+    approve: bool,
+    previous_approval: bool,
+) -> Instruction {
+    Instruction::new(
+        *owner_storage,
+        MTStorageAction::Approve {
+            transaction_hash,
+            msg_source: *owner,
+            account: *account,
+            approve,
+        },
+        Some(MTStorageAction::Approve {
+            transaction_hash: get_step_hash(transaction_hash, COMPENSATION_STEP),
+            msg_source: *owner,
+            account: *account,
+            approve: previous_approval,
+        }),
     )
 }
@@ -6,14 +6,18 @@ use gmeta::{In, InOut, Metadata};
 use gstd::{prelude::*, ActorId, Decode, Encode, TypeInfo};
 pub use instruction::*;
 use mt_main_io::LogicAction;
-pub use mt_storage_io::TokenId;
+pub use mt_main_io::{
+    Fee, NFTItemMetadata, Royalty, SwapLeg, TokenMetadata, TransferFee, BASIS_POINTS_DENOMINATOR,
+    NATIVE_TOKEN_ID,
+};
+pub use mt_storage_io::{paginate, paginate_sorted, TokenId};
 use primitive_types::H256;
 
 /// Upper bit of `TokenId` is a flag, that indicates if this is NFT or not.
 pub const NFT_BIT: TokenId = 1 << (mem::size_of::<TokenId>() * 8 - 1);
 
-/// Lower bits specifies NFT index.
-pub const NFT_INDEX_MASK: TokenId = (!0) as TokenId;
+/// Lower 64 bits(half of `TokenId`) specifies NFT index.
+pub const NFT_INDEX_MASK: TokenId = (!0_u64) as TokenId;
 
 /// Determines nft subtype by upper 64 bits(half of `TokenId`).
 pub const NFT_TYPE_MASK: TokenId = ((!0) as TokenId) << 64;
@@ -50,7 +54,7 @@ pub struct MTLogicState {
     /// Stores abstract transactions statuses.
     pub transaction_status: Vec<(H256, TransactionStatus)>,
     /// Stores instructions which may contain a few multitoken operations.
-    pub instructions: Vec<(H256, (Instruction, Instruction))>,
+    pub instructions: Vec<(H256, Vec<Instruction>)>,
     /// Unique hash-identifier of storage contract code.
     pub storage_code_hash: H256,
     /// Mapping with specific id to multitoken storage impl: `String` -> `ActorId`(dedicated storage contract).
@@ -63,6 +67,79 @@ pub struct MTLogicState {
     pub token_total_supply: Vec<(TokenId, u128)>,
     /// Mapping with token creators: `token_id` -> `ActorId`.
     pub token_creators: Vec<(TokenId, ActorId)>,
+    /// Mapping with token metadata: `token_id` -> [`TokenMetadata`].
+    pub token_metadata: Vec<(TokenId, TokenMetadata)>,
+    /// Tokens which metadata can't be changed anymore.
+    pub frozen_metadata: Vec<TokenId>,
+    /// Mapping with the last minted NFT index: `token_id`(NFT base type) -> `TokenId`.
+    pub nft_max_index: Vec<(TokenId, TokenId)>,
+    /// Mapping with NFT owners: `token_id`(NFT item) -> `ActorId`.
+    pub nft_owners: Vec<(TokenId, ActorId)>,
+    /// Mapping with NFT items metadata: `token_id`(NFT item) -> [`NFTItemMetadata`].
+    pub nft_metadata: Vec<(TokenId, NFTItemMetadata)>,
+    /// Mapping with NFT items owned by accounts: `ActorId` -> `Vec<TokenId>`.
+    pub account_nfts: Vec<(ActorId, Vec<TokenId>)>,
+    /// Mapping with the latest snapshot ids: `TokenId` -> `u64`.
+    pub token_snapshots: Vec<(TokenId, u64)>,
+    /// Tokens which transfers, mints and burns are frozen.
+    pub frozen_tokens: Vec<TokenId>,
+    /// Mapping with accounts frozen by token creators: `TokenId` -> `Vec<ActorId>`.
+    pub frozen_accounts: Vec<(TokenId, Vec<ActorId>)>,
+    /// Mapping with royalties of tokens and NFT items with own royalty: `TokenId` -> `Royalty`.
+    pub token_royalties: Vec<(TokenId, Royalty)>,
+    /// Mapping with fees charged from fungible token transfers: `TokenId` -> `TransferFee`.
+    pub transfer_fees: Vec<(TokenId, TransferFee)>,
+    /// Last assigned swap id.
+    pub swap_nonce: u64,
+    /// Mapping with pending swaps: `u64` -> `Swap`.
+    pub swaps: Vec<(u64, Swap)>,
+    /// Mapping with swaps whose tokens are being moved by transactions: `H256` -> `u64`.
+    pub settling_swaps: Vec<(H256, u64)>,
+    /// Mapping with NFT items represented by fractional tokens: `TokenId` -> `TokenId`.
+    pub fractional_tokens: Vec<(TokenId, TokenId)>,
+    /// Mapping with nonces of signed approvals: `ActorId` -> `u64`.
+    pub permit_nonces: Vec<(ActorId, u64)>,
+    /// Last assigned scheduled transfer id.
+    pub scheduled_transfer_nonce: u64,
+    /// Mapping with pending scheduled transfers: `u64` -> `ScheduledTransfer`.
+    pub scheduled_transfers: Vec<(u64, ScheduledTransfer)>,
+    /// Tokens which can't be transferred.
+    pub soulbound_tokens: Vec<TokenId>,
+    /// NFT types which items have quantities.
+    pub edition_tokens: Vec<TokenId>,
+    /// Mapping with edition items being minted by transactions: `H256` -> `TokenId`.
+    pub edition_mints: Vec<(H256, TokenId)>,
+    /// Tokens with enabled allowlist mode.
+    pub allowlist_tokens: Vec<TokenId>,
+    /// Mapping with accounts allowed by token creators: `TokenId` -> `Vec<ActorId>`.
+    pub token_allowlists: Vec<(TokenId, Vec<ActorId>)>,
+}
+
+/// Token information collected from [`MTLogicState`].
+#[derive(Debug, Encode, Decode, TypeInfo, Clone)]
+pub struct TokenInfo {
+    /// Token creator.
+    pub creator: ActorId,
+    /// Base URI with token metadata.
+    pub uri: String,
+    /// Token name, symbol and decimals.
+    pub metadata: TokenMetadata,
+    /// Indicates if the metadata can't be changed anymore.
+    pub is_metadata_frozen: bool,
+    /// Whether transfers, mints and burns of the token are frozen.
+    pub is_frozen: bool,
+    /// Whether the token can't be transferred.
+    pub is_soulbound: bool,
+    /// Whether NFT items of the token have quantities.
+    pub is_edition: bool,
+    /// Royalty from sales of the token.
+    pub royalty: Option<Royalty>,
+    /// Fee charged from transfers of the token.
+    pub transfer_fee: Option<TransferFee>,
+    /// Token total supply, number of existing items for NFT collections which aren't editions.
+    pub total_supply: u128,
+    /// Indicates if this token is nft.
+    pub is_nft: bool,
 }
 
 /// Sends the contract info about what it should do.
@@ -77,30 +154,161 @@ pub enum MTLogicAction {
         /// Encoded high-level [`Action`] operation.
         payload: Vec<u8>,
     },
-    /// Returns `account` token balance.
+    /// Mints `amount` of [`NATIVE_TOKEN_ID`] tokens to `account` for the value
+    /// deposited to multitoken main contract.
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be multitoken main contract.
+    ///
+    /// On success, replies with [`MTLogicEvent::Ok`].
+    Deposit {
+        /// Unique operation transaction hash.
+        transaction_hash: H256,
+        /// Account which deposited the value.
+        account: ActorId,
+        /// Deposited value.
+        amount: u128,
+    },
+    /// Burns `amount` of [`NATIVE_TOKEN_ID`] tokens of `account`, so that multitoken
+    /// main contract can send the value back.
     ///
     /// # Requirements
-    /// - `token_id` must exists in [`MTStorageState`](mt_storage_io::MTStorageState) state, in `balances` field.
+    /// - [`msg::source()`](gstd::msg::source) must be multitoken main contract.
+    /// - `account` must have enough [`NATIVE_TOKEN_ID`] tokens.
+    ///
+    /// On success, replies with [`MTLogicEvent::Ok`].
+    Withdraw {
+        /// Unique operation transaction hash.
+        transaction_hash: H256,
+        /// Account which withdraws the value.
+        account: ActorId,
+        /// Value to withdraw.
+        amount: u128,
+    },
+    /// Executes the scheduled transfer, sent as a delayed message by the contract itself.
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be multitoken logic contract.
+    ExecuteScheduledTransfer(u64),
+    /// Returns `account` token balance.
+    ///
+    /// Unknown tokens and accounts have zero balance.
     ///
     /// On success, replies with [`MTLogicEvent::Balance`].
+    /// If the storage contract fails to answer, replies with [`MTLogicEvent::Err`].
     GetBalance {
         /// Token ID to get the balance.
         token_id: TokenId,
         /// Specifies the account whose balance you want to find out.
         account: ActorId,
     },
+    /// Returns `account` balance of fungible `token_id` tokens at the moment
+    /// when `snapshot_id` was taken.
+    ///
+    /// On success, replies with [`MTLogicEvent::Balance`].
+    /// If `snapshot_id` isn't taken for `token_id` or the storage contract fails to answer,
+    /// replies with [`MTLogicEvent::Err`].
+    BalanceOfAt {
+        /// Token ID to get the balance.
+        token_id: TokenId,
+        /// Specifies the account whose balance you want to find out.
+        account: ActorId,
+        /// Snapshot ID to get the balance at.
+        snapshot_id: u64,
+    },
     /// Returns status approval for `approval_target` from `account`.
     ///
-    /// # Requirements
-    /// - `account` must exists in [`MTStorageState`](mt_storage_io::MTStorageState) state, in `approvals` field.
+    /// Accounts without any approvals are treated as not approved.
     ///
     /// On success, replies with [`MTLogicEvent::Approval`].
+    /// If the storage contract fails to answer, replies with [`MTLogicEvent::Err`].
     GetApproval {
         /// An account that provides approve.
         account: ActorId,
         /// An account that is being verified.
         approval_target: ActorId,
     },
+    /// Returns metadata of `token_id` token, NFT items share the metadata of their base type.
+    ///
+    /// On success, replies with [`MTLogicEvent::TokenMetadata`].
+    /// If the token doesn't exist, replies with [`MTLogicEvent::Err`].
+    GetTokenMetadata {
+        /// Token ID to get the metadata.
+        token_id: TokenId,
+    },
+    /// Returns the royalty receiver and amount for `token_id` sold for `sale_price`.
+    /// NFT items without own royalty use the royalty of their collection.
+    ///
+    /// On success, replies with [`MTLogicEvent::RoyaltyInfo`].
+    /// If the token doesn't exist, replies with [`MTLogicEvent::Err`].
+    RoyaltyInfo {
+        /// Token ID to get the royalty.
+        token_id: TokenId,
+        /// Sale price to calculate the royalty amount from.
+        sale_price: u128,
+    },
+    /// Returns a page of tokens held by `account`: fungible tokens with non-zero balance
+    /// ordered by token id, followed by owned NFT items ordered by token id.
+    ///
+    /// On success, replies with [`MTLogicEvent::TokensOf`].
+    /// If the storage contract fails to answer, replies with [`MTLogicEvent::Err`].
+    TokensOf {
+        /// Specifies the account whose tokens you want to find out.
+        account: ActorId,
+        /// Number of entries to skip.
+        offset: u32,
+        /// Maximum number of entries to return.
+        limit: u32,
+    },
+    /// Returns a page of `token_id` holders with their balances and the total
+    /// number of holders. Holders are ordered by storage shard and then by account.
+    ///
+    /// On success, replies with [`MTLogicEvent::HoldersOf`].
+    /// If any storage contract fails to answer, replies with [`MTLogicEvent::Err`].
+    HoldersOf {
+        /// Token ID to get the holders.
+        token_id: TokenId,
+        /// Number of entries to skip.
+        offset: u32,
+        /// Maximum number of entries to return.
+        limit: u32,
+    },
+    /// Returns a page of token URIs ordered by token id.
+    ///
+    /// On success, replies with [`MTLogicEvent::TokenUris`].
+    GetTokenUris {
+        /// Token id of the last entry of the previous page, `None` for the first page.
+        after: Option<TokenId>,
+        /// Maximum number of entries to return.
+        limit: u32,
+    },
+    /// Returns a page of tokens total supply ordered by token id.
+    ///
+    /// On success, replies with [`MTLogicEvent::TokenTotalSupply`].
+    GetTokenTotalSupply {
+        /// Token id of the last entry of the previous page, `None` for the first page.
+        after: Option<TokenId>,
+        /// Maximum number of entries to return.
+        limit: u32,
+    },
+    /// Returns a page of token creators ordered by token id.
+    ///
+    /// On success, replies with [`MTLogicEvent::TokenCreators`].
+    GetTokenCreators {
+        /// Token id of the last entry of the previous page, `None` for the first page.
+        after: Option<TokenId>,
+        /// Maximum number of entries to return.
+        limit: u32,
+    },
+    /// Returns a page of instructions ordered by transaction hash.
+    ///
+    /// On success, replies with [`MTLogicEvent::Instructions`].
+    GetInstructions {
+        /// Transaction hash of the last entry of the previous page, `None` for the first page.
+        after: Option<H256>,
+        /// Maximum number of entries to return.
+        limit: u32,
+    },
     /// Deletes the stored transaction entity with its status by unique hash.
     Clear(H256),
     /// Updates unique hash-identifier of storage contract code.
@@ -114,12 +322,112 @@ pub enum MTLogicAction {
 pub enum MTLogicEvent {
     /// Should be returned from [`MTLogicAction::Message`], if the operation is completed without errors.
     Ok,
-    /// Should be returned from [`MTLogicAction::Message`], if the operation is completed with errors.
+    /// Should be returned from [`MTLogicAction::Message`], if the operation is completed with errors,
+    /// or from a query, if the storage contract fails to answer.
     Err,
     /// Should be returned from [`MTLogicAction::GetBalance`].
     Balance(u128),
     /// Should be returned from [`MTLogicAction::GetApproval`].
     Approval(bool),
+    /// Should be returned from [`MTLogicAction::GetTokenMetadata`].
+    TokenMetadata {
+        /// Token name, symbol and decimals.
+        metadata: TokenMetadata,
+        /// Base URI with token metadata.
+        uri: String,
+        /// Indicates if the metadata can't be changed anymore.
+        is_frozen: bool,
+    },
+    /// Should be returned from [`MTLogicAction::Message`] with [`LogicAction::MintBatchNFT`],
+    /// if the operation is completed without errors.
+    NFTMinted(Vec<TokenId>),
+    /// Should be returned from [`MTLogicAction::Message`] with [`LogicAction::UpdateTokenUri`],
+    /// if the operation is completed without errors.
+    TokenUriChanged {
+        /// Token ID which URI has been changed.
+        token_id: TokenId,
+        /// New base URI with token metadata.
+        uri: String,
+    },
+    /// Should be returned from [`MTLogicAction::Message`] with [`LogicAction::Snapshot`],
+    /// if the operation is completed without errors.
+    SnapshotTaken {
+        /// Token ID which balances have been recorded.
+        token_id: TokenId,
+        /// Assigned snapshot ID.
+        snapshot_id: u64,
+    },
+    /// Should be returned from [`MTLogicAction::RoyaltyInfo`].
+    RoyaltyInfo {
+        /// Account which receives the royalty.
+        receiver: ActorId,
+        /// Royalty amount.
+        amount: u128,
+    },
+    /// Should be returned from [`MTLogicAction::Message`] with [`LogicAction::ProposeSwap`],
+    /// if the operation is completed without errors.
+    SwapProposed(u64),
+    /// Should be returned from [`MTLogicAction::Message`] with [`LogicAction::ScheduleTransfer`],
+    /// if the operation is completed without errors.
+    TransferScheduled(u64),
+    /// Should be returned from [`MTLogicAction::Message`] with [`LogicAction::Fractionalize`],
+    /// if the operation is completed without errors.
+    Fractionalized(TokenId),
+    /// Returned in [`MTLogicEvent::Multicall`] for [`LogicAction::Create`] actions.
+    ///
+    /// Contains the identifier of the created token.
+    Created(TokenId),
+    /// Should be returned from [`MTLogicAction::Message`] with [`LogicAction::Multicall`],
+    /// if the operation is completed without errors.
+    ///
+    /// Contains replies to the actions in their order.
+    Multicall(Vec<MTLogicEvent>),
+    /// Should be returned from [`MTLogicAction::TokensOf`].
+    TokensOf(Vec<TokenId>),
+    /// Should be returned from [`MTLogicAction::HoldersOf`].
+    HoldersOf {
+        /// Requested page of holders with their balances.
+        holders: Vec<(ActorId, u128)>,
+        /// Total number of holders across all storages.
+        total: u32,
+    },
+    /// Should be returned from [`MTLogicAction::GetTokenUris`].
+    TokenUris(Vec<(TokenId, String)>),
+    /// Should be returned from [`MTLogicAction::GetTokenTotalSupply`].
+    TokenTotalSupply(Vec<(TokenId, u128)>),
+    /// Should be returned from [`MTLogicAction::GetTokenCreators`].
+    TokenCreators(Vec<(TokenId, ActorId)>),
+    /// Should be returned from [`MTLogicAction::GetInstructions`].
+    Instructions(Vec<(H256, Vec<Instruction>)>),
+}
+
+/// A swap waiting for confirmations of its parties.
+#[derive(Encode, Decode, TypeInfo, Clone, Debug)]
+pub struct Swap {
+    /// Token movements of the swap.
+    pub legs: Vec<SwapLeg>,
+    /// Block height after which the swap can't be confirmed anymore.
+    pub deadline: u32,
+    /// Parties of the swap with their confirmation flags,
+    /// offers of a confirmed party are escrowed by the logic.
+    pub parties: Vec<(ActorId, bool)>,
+}
+
+/// Tokens escrowed by the contract until they are transferred at `at_block`.
+#[derive(Encode, Decode, TypeInfo, Clone, Debug)]
+pub struct ScheduledTransfer {
+    /// Hash of the transaction which scheduled the transfer.
+    pub transaction_hash: H256,
+    /// Identifier of the token to transfer.
+    pub token_id: TokenId,
+    /// Account which escrowed the tokens.
+    pub sender: ActorId,
+    /// Account which receives the tokens.
+    pub recipient: ActorId,
+    /// Number of tokens to transfer.
+    pub amount: u128,
+    /// Block height at which the transfer is executed.
+    pub at_block: u32,
 }
 
 /// Initializes the contract.
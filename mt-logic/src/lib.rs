@@ -2,30 +2,125 @@
 
 mod messages;
 
-use gstd::{msg, prelude::*, prog::ProgramGenerator, ActorId};
-use hashbrown::HashMap;
+use gstd::{
+    exec, msg,
+    prelude::{collections::BTreeMap, *},
+    prog::ProgramGenerator,
+    ActorId, ReservationId,
+};
+use hashbrown::{HashMap, HashSet};
 use messages::*;
 use mt_logic_io::*;
-use mt_main_io::LogicAction;
-use mt_storage_io::TokenId;
+use mt_main_io::{permit_data, verify_signature, LogicAction};
+use mt_storage_io::{InitMTStorage, TokenId, VestingSchedule};
 use primitive_types::H256;
 
 const GAS_STORAGE_CREATION: u64 = 3_000_000_000;
+const GAS_SCHEDULED_TRANSFER: u64 = 10_000_000_000;
+/// Number of blocks the gas reservation of a scheduled transfer lives,
+/// so a transfer can't be scheduled further ahead.
+const RESERVATION_DURATION: u32 = 100;
 
 #[derive(Default)]
 struct MTLogic {
     admin: ActorId,
     mtoken_id: ActorId,
     transaction_status: HashMap<H256, TransactionStatus>,
-    instructions: HashMap<H256, (Instruction, Instruction)>,
+    instructions: BTreeMap<H256, Vec<Instruction>>,
     storage_code_hash: H256,
     id_to_storage: HashMap<String, ActorId>,
     token_nonce: TokenId,
-    token_uris: HashMap<TokenId, String>,
-    token_total_supply: HashMap<TokenId, u128>,
-    token_creators: HashMap<TokenId, ActorId>,
+    token_uris: BTreeMap<TokenId, String>,
+    token_total_supply: BTreeMap<TokenId, u128>,
+    token_creators: BTreeMap<TokenId, ActorId>,
+    token_metadata: HashMap<TokenId, TokenMetadata>,
+    frozen_metadata: HashSet<TokenId>,
     nft_max_index: HashMap<TokenId, TokenId>,
     nft_owners: HashMap<TokenId, ActorId>,
+    nft_metadata: HashMap<TokenId, NFTItemMetadata>,
+    account_nfts: HashMap<ActorId, HashSet<TokenId>>,
+    token_snapshots: HashMap<TokenId, u64>,
+    frozen_tokens: HashSet<TokenId>,
+    frozen_accounts: HashMap<TokenId, HashSet<ActorId>>,
+    allowlist_tokens: HashSet<TokenId>,
+    token_allowlists: HashMap<TokenId, HashSet<ActorId>>,
+    soulbound_tokens: HashSet<TokenId>,
+    edition_tokens: HashSet<TokenId>,
+    edition_mints: HashMap<H256, TokenId>,
+    multicalls: HashMap<H256, (MulticallChanges, Vec<MTLogicEvent>)>,
+    token_royalties: HashMap<TokenId, Royalty>,
+    transfer_fees: HashMap<TokenId, TransferFee>,
+    swap_nonce: u64,
+    swaps: HashMap<u64, Swap>,
+    settling_swaps: HashMap<H256, SwapSettlement>,
+    fractional_tokens: HashMap<TokenId, TokenId>,
+    permit_nonces: HashMap<ActorId, u64>,
+    scheduled_transfer_nonce: u64,
+    scheduled_transfers: HashMap<u64, ScheduledTransfer>,
+}
+
+/// A token registered with [`LogicAction::Create`].
+struct NewToken {
+    token_id: TokenId,
+    creator: ActorId,
+    uri: String,
+    metadata: TokenMetadata,
+    is_soulbound: bool,
+    is_edition: bool,
+    royalty: Option<Royalty>,
+}
+
+/// Tokens moved by a swap transaction, see [`LogicAction::ProposeSwap`].
+///
+/// Offers of a party are escrowed by the logic when it confirms the swap,
+/// the last confirmation moves them from the escrow to their recipients.
+struct SwapSettlement {
+    swap_id: u64,
+    /// The swap before the transaction, `None` if the transaction proposes it.
+    swap: Option<Swap>,
+    /// The swap after the transaction, `None` if the transaction settles or cancels it.
+    new_swap: Option<Swap>,
+    moves: Vec<SwapLeg>,
+    reply: MTLogicEvent,
+}
+
+/// Changes of the logic state made by [`LogicAction::Multicall`] actions,
+/// which are applied only after all instructions of the transaction are executed.
+///
+/// Token nonces, NFT indexes and permit nonces are reserved in the logic state before
+/// the instructions are executed, supplies are changed by the minted and burned amounts.
+#[derive(Default)]
+struct MulticallChanges {
+    token_nonce: TokenId,
+    new_tokens: Vec<NewToken>,
+    /// `ActorId` -> `(nonce before, nonce after)`.
+    permit_nonces: HashMap<ActorId, (u64, u64)>,
+    /// `TokenId` -> `(minted, burned)`.
+    token_supply_changes: HashMap<TokenId, (u128, u128)>,
+    /// `TokenId` -> `(max index before, max index after)`.
+    nft_indexes: HashMap<TokenId, (TokenId, TokenId)>,
+    nft_owners: Vec<(TokenId, ActorId)>,
+    nft_metadata: Vec<(TokenId, NFTItemMetadata)>,
+}
+
+impl MulticallChanges {
+    fn new_token(&self, token_id: TokenId) -> Option<&NewToken> {
+        self.new_tokens
+            .iter()
+            .find(|token| token.token_id == token_id)
+    }
+
+    fn mint(&mut self, token_id: TokenId, amount: u128) -> Result<(), ()> {
+        let (minted, _) = self.token_supply_changes.entry(token_id).or_default();
+        *minted = minted.checked_add(amount).ok_or(())?;
+        Ok(())
+    }
+
+    fn burn(&mut self, token_id: TokenId, amount: u128) -> Result<(), ()> {
+        let (_, burned) = self.token_supply_changes.entry(token_id).or_default();
+        *burned = burned.checked_add(amount).ok_or(())?;
+        Ok(())
+    }
 }
 
 impl MTLogic {
@@ -72,13 +167,44 @@ impl MTLogic {
                         self.approve(transaction_hash, msg_source, &account, is_approved)
                             .await
                     }
+                    LogicAction::Permit {
+                        owner,
+                        account,
+                        is_approved,
+                        deadline,
+                        signature,
+                    } => {
+                        self.permit(
+                            transaction_hash,
+                            &owner,
+                            &account,
+                            is_approved,
+                            deadline,
+                            &signature,
+                        )
+                        .await
+                    }
                     LogicAction::Create {
                         initial_amount,
                         uri,
+                        metadata,
                         is_nft,
+                        is_soulbound,
+                        is_edition,
+                        royalty,
                     } => {
                         let _token_id = self
-                            .create(transaction_hash, msg_source, initial_amount, uri, is_nft)
+                            .create(
+                                transaction_hash,
+                                msg_source,
+                                initial_amount,
+                                uri,
+                                metadata,
+                                is_nft,
+                                is_soulbound,
+                                is_edition,
+                                royalty,
+                            )
                             .await;
                     }
                     LogicAction::MintBatchFT {
@@ -89,8 +215,38 @@ impl MTLogic {
                         self.mint_batch_ft(transaction_hash, token_id, msg_source, &to, amounts)
                             .await
                     }
-                    LogicAction::MintBatchNFT { token_id, to } => {
-                        self.mint_batch_nft(transaction_hash, token_id, msg_source, &to)
+                    LogicAction::MintVested {
+                        token_id,
+                        to,
+                        amount,
+                        cliff,
+                        duration,
+                    } => {
+                        self.mint_vested(
+                            transaction_hash,
+                            token_id,
+                            msg_source,
+                            &to,
+                            amount,
+                            cliff,
+                            duration,
+                        )
+                        .await
+                    }
+                    LogicAction::MintEdition {
+                        token_id,
+                        to,
+                        amounts,
+                    } => {
+                        self.mint_edition(transaction_hash, token_id, msg_source, &to, amounts)
+                            .await
+                    }
+                    LogicAction::MintBatchNFT {
+                        token_id,
+                        to,
+                        metadata,
+                    } => {
+                        self.mint_batch_nft(transaction_hash, token_id, msg_source, &to, metadata)
                             .await
                     }
                     LogicAction::BurnBatchFT {
@@ -111,6 +267,102 @@ impl MTLogic {
                         self.burn_nft(transaction_hash, token_id, msg_source, &from)
                             .await
                     }
+                    LogicAction::BurnBatchNFT { ids, from } => {
+                        self.burn_batch_nft(transaction_hash, msg_source, ids, from)
+                            .await
+                    }
+                    LogicAction::BurnAllNFT { from } => {
+                        self.burn_all_nft(transaction_hash, msg_source, &from).await
+                    }
+                    LogicAction::Fractionalize { token_id, supply } => {
+                        self.fractionalize(transaction_hash, token_id, msg_source, supply)
+                            .await
+                    }
+                    LogicAction::Redeem { token_id } => {
+                        self.redeem(transaction_hash, token_id, msg_source).await
+                    }
+                    LogicAction::UpdateTokenUri { token_id, uri } => {
+                        self.update_token_uri(transaction_hash, token_id, msg_source, uri)
+                    }
+                    LogicAction::FreezeMetadata { token_id } => {
+                        self.freeze_metadata(transaction_hash, token_id, msg_source)
+                    }
+                    LogicAction::SetTransferFee { token_id, fee } => {
+                        self.set_transfer_fee(transaction_hash, token_id, msg_source, fee)
+                    }
+                    LogicAction::SetItemRoyalty { token_id, royalty } => {
+                        self.set_item_royalty(transaction_hash, token_id, msg_source, royalty)
+                    }
+                    LogicAction::FreezeToken {
+                        token_id,
+                        is_frozen,
+                    } => self.freeze_token(transaction_hash, token_id, msg_source, is_frozen),
+                    LogicAction::FreezeAccount {
+                        token_id,
+                        account,
+                        is_frozen,
+                    } => self.freeze_account(
+                        transaction_hash,
+                        token_id,
+                        msg_source,
+                        &account,
+                        is_frozen,
+                    ),
+                    LogicAction::SetAllowlistMode {
+                        token_id,
+                        is_enabled,
+                    } => {
+                        self.set_allowlist_mode(transaction_hash, token_id, msg_source, is_enabled)
+                    }
+                    LogicAction::UpdateAllowlist {
+                        token_id,
+                        accounts,
+                        is_allowed,
+                    } => self.update_allowlist(
+                        transaction_hash,
+                        token_id,
+                        msg_source,
+                        accounts,
+                        is_allowed,
+                    ),
+                    LogicAction::ProposeSwap { legs, deadline } => {
+                        self.propose_swap(transaction_hash, msg_source, legs, deadline)
+                            .await
+                    }
+                    LogicAction::ConfirmSwap { swap_id } => {
+                        self.confirm_swap(transaction_hash, swap_id, msg_source)
+                            .await
+                    }
+                    LogicAction::CancelSwap { swap_id } => {
+                        self.cancel_swap(transaction_hash, swap_id, msg_source)
+                            .await
+                    }
+                    LogicAction::ScheduleTransfer {
+                        token_id,
+                        recipient,
+                        amount,
+                        at_block,
+                    } => {
+                        self.schedule_transfer(
+                            transaction_hash,
+                            msg_source,
+                            token_id,
+                            &recipient,
+                            amount,
+                            at_block,
+                        )
+                        .await
+                    }
+                    LogicAction::CancelScheduledTransfer { transfer_id } => {
+                        self.cancel_scheduled_transfer(transaction_hash, transfer_id, msg_source)
+                            .await
+                    }
+                    LogicAction::Multicall(actions) => {
+                        self.multicall(transaction_hash, msg_source, actions).await
+                    }
+                    LogicAction::Snapshot { token_id } => {
+                        self.snapshot(transaction_hash, token_id, msg_source)
+                    }
                 }
             }
         }
@@ -125,9 +377,24 @@ impl MTLogic {
         recipient: &ActorId,
         amount: u128,
     ) {
-        if Self::is_nft(token_id) {
+        if !self.is_transferable(token_id, sender, recipient) {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        if Self::is_nft(token_id) && !self.is_known_nft_item(token_id) {
+            // Error, token not found
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        if !self.has_balances(token_id) {
             // 1. Check that `msg_source` is eq to `sender` or approved
-            if !self.is_approved(sender, msg_source).await {
+            if self.is_approved(sender, msg_source).await != Ok(true) {
                 // Error, not approved
                 self.transaction_status
                     .insert(transaction_hash, TransactionStatus::Failure);
@@ -153,7 +420,7 @@ impl MTLogic {
             }
 
             // 3. Set `token_id` nft owner to `recipient`
-            self.nft_owners.insert(token_id, *recipient);
+            self.set_nft_owner(token_id, Some(*recipient));
 
             self.transaction_status
                 .insert(transaction_hash, TransactionStatus::Success);
@@ -161,14 +428,35 @@ impl MTLogic {
             return;
         }
 
+        let (treasury, fee) = self
+            .transfer_fees
+            .get(&token_id)
+            .map(|transfer_fee| (transfer_fee.treasury, transfer_fee.amount(amount)))
+            .unwrap_or_default();
+
+        if fee > amount {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
         let sender_storage_id = self.get_or_create_storage_address(sender);
         let recipient_storage_id = self.get_or_create_storage_address(recipient);
+        let treasury_storage_id = if fee != 0 {
+            Some(self.get_or_create_storage_address(&treasury))
+        } else {
+            None
+        };
 
-        if recipient_storage_id == sender_storage_id {
+        let snapshot_id = self.get_snapshot_id(token_id);
+
+        if recipient_storage_id == sender_storage_id && treasury_storage_id.is_none() {
             self.transfer_single_storage(
                 transaction_hash,
                 &sender_storage_id,
                 token_id,
+                snapshot_id,
                 msg_source,
                 sender,
                 recipient,
@@ -178,48 +466,58 @@ impl MTLogic {
             return;
         }
 
-        let (decrease_instruction, increase_instruction) = self
+        let instructions = self
             .instructions
             .entry(transaction_hash)
             .or_insert_with(|| {
-                let decrease_instruction = create_decrease_instruction(
-                    transaction_hash,
-                    &sender_storage_id,
-                    token_id,
-                    msg_source,
-                    sender,
-                    amount,
-                );
-                let increase_instruction = create_increase_instruction(
-                    transaction_hash,
-                    &recipient_storage_id,
-                    token_id,
-                    recipient,
-                    amount,
-                );
-                (decrease_instruction, increase_instruction)
-            });
+                let mut instructions = vec![
+                    create_decrease_instruction(
+                        transaction_hash,
+                        &sender_storage_id,
+                        token_id,
+                        snapshot_id,
+                        msg_source,
+                        sender,
+                        amount,
+                    ),
+                    create_increase_instruction(
+                        get_step_hash(transaction_hash, 1),
+                        &recipient_storage_id,
+                        token_id,
+                        snapshot_id,
+                        recipient,
+                        amount - fee,
+                    ),
+                ];
+
+                // The fee is an extra leg, so it's compensated together with the transfer
+                if let Some(treasury_storage_id) = treasury_storage_id {
+                    instructions.push(create_increase_instruction(
+                        get_step_hash(transaction_hash, 2),
+                        &treasury_storage_id,
+                        token_id,
+                        snapshot_id,
+                        &treasury,
+                        fee,
+                    ));
+                }
 
-        if decrease_instruction.start().await.is_err() {
-            self.transaction_status
-                .insert(transaction_hash, TransactionStatus::Failure);
-            reply_err();
-            return;
-        }
+                instructions
+            });
 
-        match increase_instruction.start().await {
-            Err(_) => {
-                if decrease_instruction.abort().await.is_ok() {
-                    self.transaction_status
-                        .insert(transaction_hash, TransactionStatus::Failure);
-                    reply_err();
-                }
-            }
-            Ok(_) => {
+        match execute_instructions(instructions).await {
+            ExecutionResult::Success => {
                 self.transaction_status
                     .insert(transaction_hash, TransactionStatus::Success);
                 reply_ok();
             }
+            ExecutionResult::Aborted => {
+                self.transaction_status
+                    .insert(transaction_hash, TransactionStatus::Failure);
+                reply_err();
+            }
+            // The transaction stays in progress to be retried
+            ExecutionResult::AbortFailed => {}
         }
     }
 
@@ -229,6 +527,7 @@ impl MTLogic {
         transaction_hash: H256,
         storage_id: &ActorId,
         token_id: u128,
+        snapshot_id: u64,
         msg_source: &ActorId,
         sender: &ActorId,
         recipient: &ActorId,
@@ -238,6 +537,7 @@ impl MTLogic {
             storage_id,
             transaction_hash,
             token_id,
+            snapshot_id,
             msg_source,
             sender,
             recipient,
@@ -293,34 +593,90 @@ impl MTLogic {
         }
     }
 
+    async fn permit(
+        &mut self,
+        transaction_hash: H256,
+        owner: &ActorId,
+        account: &ActorId,
+        is_approved: bool,
+        deadline: u32,
+        signature: &[u8; 64],
+    ) {
+        let nonce = self.permit_nonces.get(owner).copied().unwrap_or(0);
+        let data = permit_data(
+            &self.mtoken_id,
+            owner,
+            account,
+            is_approved,
+            nonce,
+            deadline,
+        );
+
+        if exec::block_height() > deadline || !verify_signature(owner, &data, signature) {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        let storage_id = self.get_or_create_storage_address(owner);
+
+        // The nonce is used up only on success, so that a retry has the same signature
+        match approve(&storage_id, transaction_hash, owner, account, is_approved).await {
+            Ok(()) => {
+                self.permit_nonces
+                    .insert(*owner, nonce.checked_add(1).expect("Math overflow!"));
+
+                self.transaction_status
+                    .insert(transaction_hash, TransactionStatus::Success);
+                reply_ok();
+            }
+            Err(()) => {
+                self.transaction_status
+                    .insert(transaction_hash, TransactionStatus::Failure);
+                reply_err();
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
     async fn create(
         &mut self,
         transaction_hash: H256,
         msg_source: &ActorId,
         initial_amount: u128,
         uri: String,
+        metadata: TokenMetadata,
         is_nft: bool,
+        is_soulbound: bool,
+        is_edition: bool,
+        royalty: Option<Royalty>,
     ) -> TokenId {
         self.transaction_status
             .insert(transaction_hash, TransactionStatus::InProgress);
 
-        let next_nonce = self.token_nonce.checked_add(1).expect("Math overflow!");
-
-        // Store the type in the upper 64 bits
-        // Before: 0 0 0 0 0 0 1
-        // After:  0 0 0 1 0 0 0
-        let mut token_type = next_nonce << (mem::size_of::<TokenId>() * 8 / 2);
-
-        // Set a flag, if this is an NFT
-        if is_nft {
-            token_type |= NFT_BIT;
+        if royalty.map_or(false, |royalty| {
+            royalty.basis_points > BASIS_POINTS_DENOMINATOR
+        }) {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return 0;
         }
 
-        let token_id = token_type;
+        let next_nonce = self.token_nonce.checked_add(1).expect("Math overflow!");
+        let token_id = Self::get_token_type(next_nonce, is_nft);
         self.token_nonce = next_nonce;
 
-        self.token_uris.insert(token_id, uri);
-        self.token_creators.insert(token_id, *msg_source);
+        self.insert_token(NewToken {
+            token_id,
+            creator: *msg_source,
+            uri,
+            metadata,
+            is_soulbound,
+            is_edition: is_nft && is_edition,
+            royalty,
+        });
 
         if !is_nft {
             self.token_total_supply.insert(token_id, initial_amount);
@@ -330,6 +686,7 @@ impl MTLogic {
                 transaction_hash,
                 &to_storage_id,
                 token_id,
+                self.get_snapshot_id(token_id),
                 msg_source,
                 initial_amount,
             );
@@ -349,6 +706,21 @@ impl MTLogic {
         token_id
     }
 
+    fn insert_token(&mut self, token: NewToken) {
+        self.token_uris.insert(token.token_id, token.uri);
+        self.token_creators.insert(token.token_id, token.creator);
+        self.token_metadata.insert(token.token_id, token.metadata);
+        if token.is_soulbound {
+            self.soulbound_tokens.insert(token.token_id);
+        }
+        if token.is_edition {
+            self.edition_tokens.insert(token.token_id);
+        }
+        if let Some(royalty) = token.royalty {
+            self.token_royalties.insert(token.token_id, royalty);
+        }
+    }
+
     async fn mint_batch_ft(
         &mut self,
         transaction_hash: H256,
@@ -360,7 +732,15 @@ impl MTLogic {
         self.transaction_status
             .insert(transaction_hash, TransactionStatus::InProgress);
 
-        if to.len() != amounts.len() || msg_source.is_zero() || !Self::is_ft(token_id) {
+        if to.len() != amounts.len()
+            || msg_source.is_zero()
+            || !Self::is_ft(token_id)
+            || token_id == NATIVE_TOKEN_ID
+            || self.fractional_tokens.contains_key(&token_id)
+            || to
+                .iter()
+                .any(|to| self.is_frozen(token_id, to) || !self.is_allowed(token_id, to))
+        {
             self.transaction_status
                 .insert(transaction_hash, TransactionStatus::Failure);
             reply_err();
@@ -373,8 +753,14 @@ impl MTLogic {
             let amount = amounts[i];
 
             let to_storage_id = self.get_or_create_storage_address(to);
-            let mut increase_instruction =
-                create_increase_instruction(transaction_hash, &to_storage_id, token_id, to, amount);
+            let mut increase_instruction = create_increase_instruction(
+                get_step_hash(transaction_hash, i as u32),
+                &to_storage_id,
+                token_id,
+                self.get_snapshot_id(token_id),
+                to,
+                amount,
+            );
 
             let token_total_supply = self
                 .token_total_supply
@@ -399,154 +785,2008 @@ impl MTLogic {
         reply_ok();
     }
 
-    async fn mint_batch_nft(
+    #[allow(clippy::too_many_arguments)]
+    async fn mint_vested(
         &mut self,
         transaction_hash: H256,
         token_id: TokenId,
-        _msg_source: &ActorId,
-        to: &Vec<ActorId>,
+        msg_source: &ActorId,
+        to: &ActorId,
+        amount: u128,
+        cliff: u32,
+        duration: u32,
     ) {
         self.transaction_status
             .insert(transaction_hash, TransactionStatus::InProgress);
 
-        if !Self::is_nft(token_id) {
+        if !Self::is_ft(token_id)
+            || token_id == NATIVE_TOKEN_ID
+            || self.fractional_tokens.contains_key(&token_id)
+            || self.token_creators.get(&token_id) != Some(msg_source)
+            || amount == 0
+            || duration == 0
+            || cliff > duration
+            || self.is_frozen(token_id, to)
+            || !self.is_allowed(token_id, to)
+        {
             self.transaction_status
                 .insert(transaction_hash, TransactionStatus::Failure);
             reply_err();
             return;
         }
 
-        // TODO: Check if `msg_source` can mint `token_id` token
-
-        let index = self
-            .nft_max_index
+        let new_token_total_supply = self
+            .token_total_supply
             .get(&token_id)
-            .unwrap_or(&0)
-            .checked_add(1)
+            .expect("Unable to locate token.")
+            .checked_add(amount)
             .expect("Math overflow!");
-        self.nft_max_index.insert(
+        let schedule = VestingSchedule {
+            amount,
+            start: exec::block_height(),
+            cliff,
+            duration,
+        };
+
+        let to_storage_id = self.get_or_create_storage_address(to);
+        if increase_vested_balance(
+            &to_storage_id,
+            transaction_hash,
             token_id,
-            (to.len() as u128)
-                .checked_add(*self.nft_max_index.get(&token_id).unwrap_or(&0))
-                .expect("Math overflow!"),
-        );
-
-        for (i, to) in to.iter().enumerate() {
-            let id = token_id | (index + i as TokenId);
-
-            self.nft_owners.insert(id, *to);
+            self.get_snapshot_id(token_id),
+            to,
+            schedule,
+        )
+        .await
+        .is_err()
+        {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
         }
 
+        self.token_total_supply
+            .insert(token_id, new_token_total_supply);
+
         self.transaction_status
             .insert(transaction_hash, TransactionStatus::Success);
         reply_ok();
     }
 
-    async fn burn_batch_ft(
+    async fn mint_edition(
         &mut self,
         transaction_hash: H256,
         token_id: TokenId,
         msg_source: &ActorId,
-        burn_from: &Vec<ActorId>,
+        to: &Vec<ActorId>,
         amounts: Vec<u128>,
     ) {
         self.transaction_status
             .insert(transaction_hash, TransactionStatus::InProgress);
 
-        if burn_from.len() != amounts.len() || msg_source.is_zero() || !Self::is_ft(token_id) {
-            self.transaction_status
-                .insert(transaction_hash, TransactionStatus::Failure);
-            reply_err();
-            return;
+        // A retry continues minting the item chosen by the transaction
+        let id = match self.edition_mints.get(&transaction_hash) {
+            Some(id) => *id,
+            None => {
+                let base_type = Self::get_nft_base_type(token_id);
+
+                if to.len() != amounts.len()
+                    || !self.is_edition(token_id)
+                    || self.token_creators.get(&base_type) != Some(msg_source)
+                    || (Self::is_nft_item(token_id) && !self.is_known_nft_item(token_id))
+                    || to
+                        .iter()
+                        .any(|to| self.is_frozen(token_id, to) || !self.is_allowed(token_id, to))
+                {
+                    self.transaction_status
+                        .insert(transaction_hash, TransactionStatus::Failure);
+                    reply_err();
+                    return;
+                }
+
+                // A new item is minted for an NFT type, its index is reserved before awaits
+                let id = if Self::is_nft_base_type(token_id) {
+                    let index = self
+                        .nft_max_index
+                        .get(&token_id)
+                        .unwrap_or(&0)
+                        .checked_add(1)
+                        .expect("Math overflow!");
+                    self.nft_max_index.insert(token_id, index);
+
+                    token_id | index
+                } else {
+                    token_id
+                };
+                self.edition_mints.insert(transaction_hash, id);
+
+                id
+            }
+        };
+
+        let mut instructions = Vec::with_capacity(to.len());
+        for (i, (to, amount)) in to.iter().zip(&amounts).enumerate() {
+            let to_storage_id = self.get_or_create_storage_address(to);
+            instructions.push(create_increase_instruction(
+                get_step_hash(transaction_hash, i as u32),
+                &to_storage_id,
+                id,
+                self.get_snapshot_id(id),
+                to,
+                *amount,
+            ));
         }
 
-        for (i, from) in burn_from.iter().enumerate() {
-            let amount = amounts[i];
+        let instructions = self
+            .instructions
+            .entry(transaction_hash)
+            .or_insert(instructions);
+
+        match execute_instructions(instructions).await {
+            ExecutionResult::Success => {
+                self.edition_mints.remove(&transaction_hash);
+
+                // Supplies are changed by the minted amount, since other mints may run meanwhile.
+                // Copies of edition items are counted in the supply of their collection too
+                let minted = amounts
+                    .iter()
+                    .try_fold(0, |minted: u128, amount| minted.checked_add(*amount))
+                    .expect("Math overflow!");
+                for supply_id in [id, Self::get_nft_base_type(id)] {
+                    let token_total_supply = self.token_total_supply.entry(supply_id).or_default();
+                    *token_total_supply = token_total_supply
+                        .checked_add(minted)
+                        .expect("Math overflow!");
+                }
+
+                self.transaction_status
+                    .insert(transaction_hash, TransactionStatus::Success);
+                msg::reply(MTLogicEvent::NFTMinted(vec![id]), 0)
+                    .expect("Error in a reply `MTLogicEvent::NFTMinted`.");
+            }
+            ExecutionResult::Aborted => {
+                self.edition_mints.remove(&transaction_hash);
+
+                // The reserved index is released, unless a later item is minted already
+                if id != token_id {
+                    let index = Self::get_nft_index(id);
+                    if self.nft_max_index.get(&token_id) == Some(&index) {
+                        self.nft_max_index.insert(token_id, index - 1);
+                    }
+                }
 
-            if !self.is_approved(from, msg_source).await {
                 self.transaction_status
                     .insert(transaction_hash, TransactionStatus::Failure);
                 reply_err();
-                return;
             }
+            // The transaction stays in progress to be retried
+            ExecutionResult::AbortFailed => {}
+        }
+    }
+
+    async fn mint_batch_nft(
+        &mut self,
+        transaction_hash: H256,
+        token_id: TokenId,
+        _msg_source: &ActorId,
+        to: &Vec<ActorId>,
+        metadata: Vec<Option<NFTItemMetadata>>,
+    ) {
+        self.transaction_status
+            .insert(transaction_hash, TransactionStatus::InProgress);
+
+        if !Self::is_nft_base_type(token_id)
+            || !self.token_creators.contains_key(&token_id)
+            || self.edition_tokens.contains(&token_id)
+            || (!metadata.is_empty() && metadata.len() != to.len())
+            || to
+                .iter()
+                .any(|to| self.is_frozen(token_id, to) || !self.is_allowed(token_id, to))
+        {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        // TODO: Check if `msg_source` can mint `token_id` token
+
+        let index = self
+            .nft_max_index
+            .get(&token_id)
+            .unwrap_or(&0)
+            .checked_add(1)
+            .expect("Math overflow!");
+        self.nft_max_index.insert(
+            token_id,
+            (to.len() as u128)
+                .checked_add(*self.nft_max_index.get(&token_id).unwrap_or(&0))
+                .expect("Math overflow!"),
+        );
+
+        let token_total_supply = self.token_total_supply.entry(token_id).or_default();
+        *token_total_supply = token_total_supply
+            .checked_add(to.len() as u128)
+            .expect("Math overflow!");
+
+        let mut ids = Vec::with_capacity(to.len());
+        for (i, to) in to.iter().enumerate() {
+            let id = token_id | (index + i as TokenId);
+
+            self.set_nft_owner(id, Some(*to));
+            ids.push(id);
+        }
+
+        for (id, metadata) in ids.iter().zip(metadata) {
+            if let Some(metadata) = metadata {
+                self.nft_metadata.insert(*id, metadata);
+            }
+        }
+
+        self.transaction_status
+            .insert(transaction_hash, TransactionStatus::Success);
+        msg::reply(MTLogicEvent::NFTMinted(ids), 0)
+            .expect("Error in a reply `MTLogicEvent::NFTMinted`.");
+    }
+
+    async fn burn_batch_ft(
+        &mut self,
+        transaction_hash: H256,
+        token_id: TokenId,
+        msg_source: &ActorId,
+        burn_from: &Vec<ActorId>,
+        amounts: Vec<u128>,
+    ) {
+        self.transaction_status
+            .insert(transaction_hash, TransactionStatus::InProgress);
+
+        if burn_from.len() != amounts.len()
+            || msg_source.is_zero()
+            || !self.has_balances(token_id)
+            || token_id == NATIVE_TOKEN_ID
+            || burn_from.iter().any(|from| self.is_frozen(token_id, from))
+        {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        for (i, from) in burn_from.iter().enumerate() {
+            let amount = amounts[i];
+
+            if self.is_approved(from, msg_source).await != Ok(true) {
+                self.transaction_status
+                    .insert(transaction_hash, TransactionStatus::Failure);
+                reply_err();
+                return;
+            }
+
+            let from_storage_id = self.get_or_create_storage_address(from);
+            let mut decrease_instruction = create_decrease_instruction(
+                get_step_hash(transaction_hash, i as u32),
+                &from_storage_id,
+                token_id,
+                self.get_snapshot_id(token_id),
+                msg_source,
+                from,
+                amount,
+            );
+
+            let token_total_supply = self
+                .token_total_supply
+                .get_mut(&token_id)
+                .expect("Unable to locate token.");
+            let new_token_total_supply = token_total_supply
+                .checked_sub(amount)
+                .expect("Math overflow!");
+
+            if decrease_instruction.start().await.is_err() {
+                self.transaction_status
+                    .insert(transaction_hash, TransactionStatus::Failure);
+                reply_err();
+                return;
+            }
+
+            *token_total_supply = new_token_total_supply;
+
+            if self.is_edition(token_id) {
+                self.burn_edition_copies(token_id, amount);
+            }
+        }
+
+        self.transaction_status
+            .insert(transaction_hash, TransactionStatus::Success);
+        reply_ok();
+    }
+
+    async fn burn_nft(
+        &mut self,
+        transaction_hash: H256,
+        token_id: TokenId,
+        msg_source: &ActorId,
+        from: &ActorId,
+    ) {
+        self.transaction_status
+            .insert(transaction_hash, TransactionStatus::InProgress);
+
+        if !self.is_known_nft_item(token_id)
+            || self.is_edition(token_id)
+            || self.is_frozen(token_id, from)
+        {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        // 1. Check that `msg_source` is eq to `from` or approved
+        if self.is_approved(from, msg_source).await != Ok(true) {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        // 2. Check that `token_id` nft owner is `from`
+        if let Some(nft_owner) = self.nft_owners.get(&token_id) {
+            if nft_owner != from {
+                // Error, invalid owner
+                self.transaction_status
+                    .insert(transaction_hash, TransactionStatus::Failure);
+                reply_err();
+                return;
+            }
+        } else {
+            // Error, token not found
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        // 3. Remove `token_id` nft
+        self.remove_nft(token_id);
+
+        self.transaction_status
+            .insert(transaction_hash, TransactionStatus::Success);
+        reply_ok();
+    }
+
+    async fn burn_batch_nft(
+        &mut self,
+        transaction_hash: H256,
+        msg_source: &ActorId,
+        ids: Vec<TokenId>,
+        from: Vec<ActorId>,
+    ) {
+        if ids.len() != from.len() {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        self.burn_nfts(
+            transaction_hash,
+            msg_source,
+            ids.into_iter().zip(from).collect(),
+        )
+        .await;
+    }
+
+    async fn burn_all_nft(&mut self, transaction_hash: H256, msg_source: &ActorId, from: &ActorId) {
+        let nfts = self
+            .account_nfts
+            .get(from)
+            .map(|nfts| nfts.iter().map(|id| (*id, *from)).collect())
+            .unwrap_or_default();
+
+        self.burn_nfts(transaction_hash, msg_source, nfts).await;
+    }
+
+    /// Burns all `nfts` with their owners, if every of them can be burned by `msg_source`.
+    async fn burn_nfts(
+        &mut self,
+        transaction_hash: H256,
+        msg_source: &ActorId,
+        nfts: Vec<(TokenId, ActorId)>,
+    ) {
+        // 1. Check that `msg_source` is eq to every owner or approved
+        let owners: HashSet<ActorId> = nfts.iter().map(|(_, from)| *from).collect();
+        for from in owners {
+            if self.is_approved(&from, msg_source).await != Ok(true) {
+                self.transaction_status
+                    .insert(transaction_hash, TransactionStatus::Failure);
+                reply_err();
+                return;
+            }
+        }
+
+        // 2. Check that every nft is owned by its `from` after approvals are received
+        let mut ids = HashSet::with_capacity(nfts.len());
+        for (token_id, from) in &nfts {
+            if !ids.insert(*token_id)
+                || !self.is_known_nft_item(*token_id)
+                || self.is_edition(*token_id)
+                || self.is_frozen(*token_id, from)
+                || self.nft_owners.get(token_id) != Some(from)
+            {
+                self.transaction_status
+                    .insert(transaction_hash, TransactionStatus::Failure);
+                reply_err();
+                return;
+            }
+        }
+
+        // 3. Remove all nfts
+        for token_id in ids {
+            self.remove_nft(token_id);
+        }
+
+        self.transaction_status
+            .insert(transaction_hash, TransactionStatus::Success);
+        reply_ok();
+    }
+
+    /// Decreases the supply of the edition item collection, which counts copies of all its items.
+    /// Edition items are burned with [`LogicAction::BurnBatchFT`] instead of [`Self::remove_nft`].
+    fn burn_edition_copies(&mut self, token_id: TokenId, amount: u128) {
+        if let Some(token_total_supply) = self
+            .token_total_supply
+            .get_mut(&Self::get_nft_base_type(token_id))
+        {
+            *token_total_supply = token_total_supply.saturating_sub(amount);
+        }
+    }
+
+    fn remove_nft(&mut self, token_id: TokenId) {
+        self.set_nft_owner(token_id, None);
+        self.nft_metadata.remove(&token_id);
+        self.token_royalties.remove(&token_id);
+
+        if let Some(token_total_supply) = self
+            .token_total_supply
+            .get_mut(&Self::get_nft_base_type(token_id))
+        {
+            *token_total_supply = token_total_supply.saturating_sub(1);
+        }
+    }
+
+    fn update_token_uri(
+        &mut self,
+        transaction_hash: H256,
+        token_id: TokenId,
+        msg_source: &ActorId,
+        uri: String,
+    ) {
+        if self.token_creators.get(&token_id) != Some(msg_source)
+            || self.frozen_metadata.contains(&token_id)
+        {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        self.token_uris.insert(token_id, uri.clone());
+
+        self.transaction_status
+            .insert(transaction_hash, TransactionStatus::Success);
+        msg::reply(MTLogicEvent::TokenUriChanged { token_id, uri }, 0)
+            .expect("Error in a reply `MTLogicEvent::TokenUriChanged`.");
+    }
+
+    fn freeze_metadata(&mut self, transaction_hash: H256, token_id: TokenId, msg_source: &ActorId) {
+        if self.token_creators.get(&token_id) != Some(msg_source) {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        self.frozen_metadata.insert(token_id);
+
+        self.transaction_status
+            .insert(transaction_hash, TransactionStatus::Success);
+        reply_ok();
+    }
+
+    fn set_item_royalty(
+        &mut self,
+        transaction_hash: H256,
+        token_id: TokenId,
+        msg_source: &ActorId,
+        royalty: Option<Royalty>,
+    ) {
+        if !self.is_known_nft_item(token_id)
+            || self.token_creators.get(&Self::get_nft_base_type(token_id)) != Some(msg_source)
+            || royalty.map_or(false, |royalty| {
+                royalty.basis_points > BASIS_POINTS_DENOMINATOR
+            })
+        {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        match royalty {
+            Some(royalty) => self.token_royalties.insert(token_id, royalty),
+            None => self.token_royalties.remove(&token_id),
+        };
+
+        self.transaction_status
+            .insert(transaction_hash, TransactionStatus::Success);
+        reply_ok();
+    }
+
+    fn set_transfer_fee(
+        &mut self,
+        transaction_hash: H256,
+        token_id: TokenId,
+        msg_source: &ActorId,
+        fee: Option<TransferFee>,
+    ) {
+        let is_valid_fee = match fee.map(|transfer_fee| transfer_fee.fee) {
+            Some(Fee::BasisPoints(basis_points)) => basis_points <= BASIS_POINTS_DENOMINATOR,
+            _ => true,
+        };
+
+        if !Self::is_ft(token_id)
+            || self.token_creators.get(&token_id) != Some(msg_source)
+            || !is_valid_fee
+        {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        match fee {
+            Some(fee) => self.transfer_fees.insert(token_id, fee),
+            None => self.transfer_fees.remove(&token_id),
+        };
+
+        self.transaction_status
+            .insert(transaction_hash, TransactionStatus::Success);
+        reply_ok();
+    }
+
+    fn freeze_token(
+        &mut self,
+        transaction_hash: H256,
+        token_id: TokenId,
+        msg_source: &ActorId,
+        is_frozen: bool,
+    ) {
+        let token_id = Self::get_nft_base_type(token_id);
+
+        if self.token_creators.get(&token_id) != Some(msg_source) {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        if is_frozen {
+            self.frozen_tokens.insert(token_id);
+        } else {
+            self.frozen_tokens.remove(&token_id);
+        }
+
+        self.transaction_status
+            .insert(transaction_hash, TransactionStatus::Success);
+        reply_ok();
+    }
+
+    fn freeze_account(
+        &mut self,
+        transaction_hash: H256,
+        token_id: TokenId,
+        msg_source: &ActorId,
+        account: &ActorId,
+        is_frozen: bool,
+    ) {
+        let token_id = Self::get_nft_base_type(token_id);
+
+        if self.token_creators.get(&token_id) != Some(msg_source) {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        if is_frozen {
+            self.frozen_accounts
+                .entry(token_id)
+                .or_default()
+                .insert(*account);
+        } else if let Some(accounts) = self.frozen_accounts.get_mut(&token_id) {
+            accounts.remove(account);
+        }
+
+        self.transaction_status
+            .insert(transaction_hash, TransactionStatus::Success);
+        reply_ok();
+    }
+
+    fn set_allowlist_mode(
+        &mut self,
+        transaction_hash: H256,
+        token_id: TokenId,
+        msg_source: &ActorId,
+        is_enabled: bool,
+    ) {
+        let token_id = Self::get_nft_base_type(token_id);
+
+        if self.token_creators.get(&token_id) != Some(msg_source) {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        if is_enabled {
+            self.allowlist_tokens.insert(token_id);
+        } else {
+            self.allowlist_tokens.remove(&token_id);
+        }
+
+        self.transaction_status
+            .insert(transaction_hash, TransactionStatus::Success);
+        reply_ok();
+    }
+
+    fn update_allowlist(
+        &mut self,
+        transaction_hash: H256,
+        token_id: TokenId,
+        msg_source: &ActorId,
+        accounts: Vec<ActorId>,
+        is_allowed: bool,
+    ) {
+        let token_id = Self::get_nft_base_type(token_id);
+
+        if self.token_creators.get(&token_id) != Some(msg_source) {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        let allowlist = self.token_allowlists.entry(token_id).or_default();
+        for account in accounts {
+            if is_allowed {
+                allowlist.insert(account);
+            } else {
+                allowlist.remove(&account);
+            }
+        }
+
+        self.transaction_status
+            .insert(transaction_hash, TransactionStatus::Success);
+        reply_ok();
+    }
+
+    async fn fractionalize(
+        &mut self,
+        transaction_hash: H256,
+        token_id: TokenId,
+        msg_source: &ActorId,
+        supply: u128,
+    ) {
+        if supply == 0
+            || !self.is_known_nft_item(token_id)
+            || self.nft_owners.get(&token_id) != Some(msg_source)
+            || !self.is_transferable(token_id, msg_source, msg_source)
+        {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        let escrow = exec::program_id();
+        let base_type = Self::get_nft_base_type(token_id);
+        let next_nonce = self.token_nonce.checked_add(1).expect("Math overflow!");
+        let fractional_token_id = next_nonce << (mem::size_of::<TokenId>() * 8 / 2);
+        self.token_nonce = next_nonce;
+
+        // Lock the item before minting, so it can't be moved meanwhile
+        self.set_nft_owner(token_id, Some(escrow));
+
+        let to_storage_id = self.get_or_create_storage_address(msg_source);
+        let mut increase_instruction = create_increase_instruction(
+            transaction_hash,
+            &to_storage_id,
+            fractional_token_id,
+            self.get_snapshot_id(fractional_token_id),
+            msg_source,
+            supply,
+        );
+
+        if increase_instruction.start().await.is_err() {
+            self.set_nft_owner(token_id, Some(*msg_source));
+
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        let uri = self.get_token_uri(base_type);
+        let metadata = self
+            .token_metadata
+            .get(&base_type)
+            .cloned()
+            .unwrap_or_default();
+
+        self.token_uris.insert(fractional_token_id, uri);
+        self.token_creators.insert(fractional_token_id, escrow);
+        self.token_metadata.insert(fractional_token_id, metadata);
+        self.token_total_supply.insert(fractional_token_id, supply);
+        self.fractional_tokens.insert(fractional_token_id, token_id);
+
+        self.transaction_status
+            .insert(transaction_hash, TransactionStatus::Success);
+        msg::reply(MTLogicEvent::Fractionalized(fractional_token_id), 0)
+            .expect("Error in a reply `MTLogicEvent::Fractionalized`.");
+    }
+
+    async fn redeem(&mut self, transaction_hash: H256, token_id: TokenId, msg_source: &ActorId) {
+        let escrow = exec::program_id();
+        let supply = self.token_total_supply.get(&token_id).copied().unwrap_or(0);
+        let nft_id = match self.fractional_tokens.get(&token_id) {
+            Some(nft_id) if self.nft_owners.get(nft_id) == Some(&escrow) => *nft_id,
+            _ => 0,
+        };
+
+        // All fractions can be burned, then the item can't be redeemed
+        if nft_id == 0 || supply == 0 || !self.is_transferable(nft_id, msg_source, msg_source) {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        let from_storage_id = self.get_or_create_storage_address(msg_source);
+        let mut decrease_instruction = create_decrease_instruction(
+            transaction_hash,
+            &from_storage_id,
+            token_id,
+            self.get_snapshot_id(token_id),
+            msg_source,
+            msg_source,
+            supply,
+        );
+
+        if decrease_instruction.start().await.is_err() {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        self.token_total_supply.insert(token_id, 0);
+        self.set_nft_owner(nft_id, Some(*msg_source));
+
+        self.transaction_status
+            .insert(transaction_hash, TransactionStatus::Success);
+        reply_ok();
+    }
+
+    async fn propose_swap(
+        &mut self,
+        transaction_hash: H256,
+        msg_source: &ActorId,
+        legs: Vec<SwapLeg>,
+        deadline: u32,
+    ) {
+        // A retry continues the escrow started by the transaction
+        if self.settling_swaps.contains_key(&transaction_hash) {
+            self.settle_swap(transaction_hash).await;
+            return;
+        }
+
+        let mut parties: Vec<(ActorId, bool)> = Vec::new();
+        for leg in &legs {
+            for account in [leg.from, leg.to] {
+                if !parties.iter().any(|(party, _)| *party == account) {
+                    parties.push((account, account == *msg_source));
+                }
+            }
+        }
+
+        let is_valid_leg = |leg: &SwapLeg| {
+            leg.from != leg.to
+                && leg.amount != 0
+                && (self.has_balances(leg.token_id) || leg.amount == 1)
+        };
+
+        if legs.is_empty()
+            || !legs.iter().all(is_valid_leg)
+            || !parties.iter().any(|(party, _)| party == msg_source)
+            || deadline <= exec::block_height()
+            || !self.is_swap_offer_transferable(&legs, msg_source)
+        {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        let swap_id = self.swap_nonce.checked_add(1).expect("Math overflow!");
+        self.swap_nonce = swap_id;
+
+        // Offers of the proposer are escrowed right away
+        let escrow = exec::program_id();
+        let moves = legs
+            .iter()
+            .filter(|leg| leg.from == *msg_source)
+            .map(|leg| SwapLeg {
+                to: escrow,
+                ..leg.clone()
+            })
+            .collect();
+
+        self.settling_swaps.insert(
+            transaction_hash,
+            SwapSettlement {
+                swap_id,
+                swap: None,
+                new_swap: Some(Swap {
+                    legs,
+                    deadline,
+                    parties,
+                }),
+                moves,
+                reply: MTLogicEvent::SwapProposed(swap_id),
+            },
+        );
+        self.settle_swap(transaction_hash).await;
+    }
+
+    async fn confirm_swap(&mut self, transaction_hash: H256, swap_id: u64, msg_source: &ActorId) {
+        // A retry continues the escrow or the settlement started by the transaction
+        if self.settling_swaps.contains_key(&transaction_hash) {
+            self.settle_swap(transaction_hash).await;
+            return;
+        }
+
+        // Offers escrowed before the deadline are released with `CancelSwap`
+        let is_confirmable = self.swaps.get(&swap_id).map_or(false, |swap| {
+            exec::block_height() <= swap.deadline
+                && swap
+                    .parties
+                    .iter()
+                    .any(|(party, is_confirmed)| party == msg_source && !is_confirmed)
+        });
+
+        if !is_confirmable {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        // The swap is taken out before the transaction awaits,
+        // so it can't be confirmed or cancelled again meanwhile
+        let swap = self.swaps.remove(&swap_id).expect("Swap must exist.");
+        let mut new_swap = swap.clone();
+        for (party, is_confirmed) in &mut new_swap.parties {
+            if party == msg_source {
+                *is_confirmed = true;
+            }
+        }
+
+        let is_settled = new_swap
+            .parties
+            .iter()
+            .all(|(_, is_confirmed)| *is_confirmed);
+        let is_transferable = if is_settled {
+            swap.legs
+                .iter()
+                .all(|leg| self.is_transferable(leg.token_id, &leg.from, &leg.to))
+        } else {
+            self.is_swap_offer_transferable(&swap.legs, msg_source)
+        };
+
+        if !is_transferable {
+            self.swaps.insert(swap_id, swap);
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        // The last confirmation moves its own offers directly and the escrowed ones
+        // from the escrow, other confirmations escrow their offers
+        let escrow = exec::program_id();
+        let moves = swap
+            .legs
+            .iter()
+            .filter_map(|leg| match (is_settled, leg.from == *msg_source) {
+                (true, true) => Some(leg.clone()),
+                (true, false) => Some(SwapLeg {
+                    from: escrow,
+                    ..leg.clone()
+                }),
+                (false, true) => Some(SwapLeg {
+                    to: escrow,
+                    ..leg.clone()
+                }),
+                (false, false) => None,
+            })
+            .collect();
+
+        self.settling_swaps.insert(
+            transaction_hash,
+            SwapSettlement {
+                swap_id,
+                swap: Some(swap),
+                new_swap: (!is_settled).then_some(new_swap),
+                moves,
+                reply: MTLogicEvent::Ok,
+            },
+        );
+        self.settle_swap(transaction_hash).await;
+    }
+
+    async fn cancel_swap(&mut self, transaction_hash: H256, swap_id: u64, msg_source: &ActorId) {
+        // A retry continues the release started by the transaction
+        if self.settling_swaps.contains_key(&transaction_hash) {
+            self.settle_swap(transaction_hash).await;
+            return;
+        }
+
+        let can_cancel = self.swaps.get(&swap_id).map_or(false, |swap| {
+            exec::block_height() > swap.deadline
+                || swap.parties.iter().any(|(party, _)| party == msg_source)
+        });
+
+        if !can_cancel {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        // Escrowed offers of the confirmed parties are returned to them
+        let swap = self.swaps.remove(&swap_id).expect("Swap must exist.");
+        let escrow = exec::program_id();
+        let moves = swap
+            .legs
+            .iter()
+            .filter(|leg| {
+                swap.parties
+                    .iter()
+                    .any(|(party, is_confirmed)| *is_confirmed && *party == leg.from)
+            })
+            .map(|leg| SwapLeg {
+                from: escrow,
+                to: leg.from,
+                ..leg.clone()
+            })
+            .collect();
+
+        self.settling_swaps.insert(
+            transaction_hash,
+            SwapSettlement {
+                swap_id,
+                swap: Some(swap),
+                new_swap: None,
+                moves,
+                reply: MTLogicEvent::Ok,
+            },
+        );
+        self.settle_swap(transaction_hash).await;
+    }
+
+    /// Executes token moves of the swap transaction and replaces the swap
+    /// with its new state on success or with its previous state otherwise.
+    async fn settle_swap(&mut self, transaction_hash: H256) {
+        let moves = self
+            .settling_swaps
+            .get(&transaction_hash)
+            .map(|settlement| settlement.moves.clone())
+            .expect("Swap must be settling.");
+        let (ft_moves, nft_moves): (Vec<SwapLeg>, Vec<SwapLeg>) = moves
+            .into_iter()
+            .partition(|leg| self.has_balances(leg.token_id));
+
+        if !self.is_swap_nft_legs_valid(&nft_moves) {
+            self.restore_swap(transaction_hash);
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        let mut storages = Vec::with_capacity(ft_moves.len());
+        for leg in &ft_moves {
+            storages.push((
+                self.get_or_create_storage_address(&leg.from),
+                self.get_or_create_storage_address(&leg.to),
+                self.get_snapshot_id(leg.token_id),
+            ));
+        }
+
+        let instructions = self
+            .instructions
+            .entry(transaction_hash)
+            .or_insert_with(|| {
+                ft_moves
+                    .iter()
+                    .zip(storages)
+                    .enumerate()
+                    .flat_map(|(i, (leg, storages))| {
+                        let (from_storage_id, to_storage_id, snapshot_id) = storages;
+                        let step = 2 * i as u32;
+                        [
+                            create_decrease_instruction(
+                                get_step_hash(transaction_hash, step),
+                                &from_storage_id,
+                                leg.token_id,
+                                snapshot_id,
+                                &leg.from,
+                                &leg.from,
+                                leg.amount,
+                            ),
+                            create_increase_instruction(
+                                get_step_hash(transaction_hash, step + 1),
+                                &to_storage_id,
+                                leg.token_id,
+                                snapshot_id,
+                                &leg.to,
+                                leg.amount,
+                            ),
+                        ]
+                    })
+                    .collect()
+            });
+
+        let mut result = execute_instructions(instructions).await;
+
+        // NFT items could be moved while fungible legs were executing
+        if result == ExecutionResult::Success && !self.is_swap_nft_legs_valid(&nft_moves) {
+            let instructions = self
+                .instructions
+                .get_mut(&transaction_hash)
+                .expect("Instructions must exist.");
+            result = match abort_instructions(instructions).await {
+                Ok(()) => ExecutionResult::Aborted,
+                Err(()) => ExecutionResult::AbortFailed,
+            };
+        }
+
+        match result {
+            ExecutionResult::Success => {
+                for leg in nft_moves {
+                    self.set_nft_owner(leg.token_id, Some(leg.to));
+                }
+
+                let settlement = self
+                    .settling_swaps
+                    .remove(&transaction_hash)
+                    .expect("Swap must be settling.");
+                if let Some(new_swap) = settlement.new_swap {
+                    self.swaps.insert(settlement.swap_id, new_swap);
+                }
+
+                self.transaction_status
+                    .insert(transaction_hash, TransactionStatus::Success);
+                msg::reply(settlement.reply, 0).expect("Error in a reply `MTLogicEvent`.");
+            }
+            // The swap can be confirmed again, e.g. when its parties have enough tokens
+            ExecutionResult::Aborted => {
+                self.restore_swap(transaction_hash);
+                self.transaction_status
+                    .insert(transaction_hash, TransactionStatus::Failure);
+                reply_err();
+            }
+            // The transaction stays in progress to be retried
+            ExecutionResult::AbortFailed => {}
+        }
+    }
+
+    /// Puts back the swap as it was before the failed transaction.
+    fn restore_swap(&mut self, transaction_hash: H256) {
+        let settlement = self
+            .settling_swaps
+            .remove(&transaction_hash)
+            .expect("Swap must be settling.");
+        if let Some(swap) = settlement.swap {
+            self.swaps.insert(settlement.swap_id, swap);
+        }
+    }
+
+    fn is_swap_offer_transferable(&self, legs: &[SwapLeg], party: &ActorId) -> bool {
+        legs.iter()
+            .filter(|leg| leg.from == *party)
+            .all(|leg| self.is_transferable(leg.token_id, &leg.from, &leg.to))
+    }
+
+    fn is_swap_nft_legs_valid(&self, nft_legs: &[SwapLeg]) -> bool {
+        nft_legs.iter().enumerate().all(|(i, leg)| {
+            self.is_known_nft_item(leg.token_id)
+                && self.nft_owners.get(&leg.token_id) == Some(&leg.from)
+                && !nft_legs[..i]
+                    .iter()
+                    .any(|other| other.token_id == leg.token_id)
+        })
+    }
+
+    async fn schedule_transfer(
+        &mut self,
+        transaction_hash: H256,
+        msg_source: &ActorId,
+        token_id: TokenId,
+        recipient: &ActorId,
+        amount: u128,
+        at_block: u32,
+    ) {
+        let block_height = exec::block_height();
+
+        if !self.has_balances(token_id)
+            || amount == 0
+            || at_block <= block_height
+            || at_block - block_height > RESERVATION_DURATION
+            || msg_source == recipient
+            || !self.is_transferable(token_id, msg_source, recipient)
+        {
+            self.transaction_status
+                .insert(transaction_hash, TransactionStatus::Failure);
+            reply_err();
+            return;
+        }
+
+        let reservation_id =
+            match ReservationId::reserve(GAS_SCHEDULED_TRANSFER, RESERVATION_DURATION) {
+                Ok(reservation_id) => reservation_id,
+                Err(_) => {
+                    self.transaction_status
+                        .insert(transaction_hash, TransactionStatus::Failure);
+                    reply_err();
+                    return;
+                }
+            };
+
+        let escrow = exec::program_id();
+        let sender_storage_id = self.get_or_create_storage_address(msg_source);
+        let escrow_storage_id = self.get_or_create_storage_address(&escrow);
+        let snapshot_id = self.get_snapshot_id(token_id);
+
+        let instructions = self
+            .instructions
+            .entry(transaction_hash)
+            .or_insert_with(|| {
+                vec![
+                    create_decrease_instruction(
+                        transaction_hash,
+                        &sender_storage_id,
+                        token_id,
+                        snapshot_id,
+                        msg_source,
+                        msg_source,
+                        amount,
+                    ),
+                    create_increase_instruction(
+                        get_step_hash(transaction_hash, 1),
+                        &escrow_storage_id,
+                        token_id,
+                        snapshot_id,
+                        &escrow,
+                        amount,
+                    ),
+                ]
+            });
+
+        match execute_instructions(instructions).await {
+            ExecutionResult::Success => {
+                let transfer_id = self
+                    .scheduled_transfer_nonce
+                    .checked_add(1)
+                    .expect("Math overflow!");
+                self.scheduled_transfer_nonce = transfer_id;
+                self.scheduled_transfers.insert(
+                    transfer_id,
+                    ScheduledTransfer {
+                        transaction_hash,
+                        token_id,
+                        sender: *msg_source,
+                        recipient: *recipient,
+                        amount,
+                        at_block,
+                    },
+                );
+
+                msg::send_delayed_from_reservation(
+                    reservation_id,
+                    escrow,
+                    MTLogicAction::ExecuteScheduledTransfer(transfer_id),
+                    0,
+                    at_block.saturating_sub(exec::block_height()),
+                )
+                .expect(
+                    "Error in sending a delayed message `MTLogicAction::ExecuteScheduledTransfer`.",
+                );
+
+                self.transaction_status
+                    .insert(transaction_hash, TransactionStatus::Success);
+                msg::reply(MTLogicEvent::TransferScheduled(transfer_id), 0)
+                    .expect("Error in a reply `MTLogicEvent::TransferScheduled`.");
+            }
+            ExecutionResult::Aborted => {
+                let _ = reservation_id.unreserve();
+
+                self.transaction_status
+                    .insert(transaction_hash, TransactionStatus::Failure);
+                reply_err();
+            }
+            // The transaction stays in progress to be retried
+            ExecutionResult::AbortFailed => {
+                let _ = reservation_id.unreserve();
+            }
+        }
+    }
+
+    async fn execute_scheduled_transfer(&mut self, transfer_id: u64) {
+        let escrow = exec::program_id();
+        assert!(
+            msg::source() == escrow,
+            "Only multitoken logic contract is allowed to call that action."
+        );
+
+        // The transfer is cancelled
+        let transfer = match self.scheduled_transfers.remove(&transfer_id) {
+            Some(transfer) => transfer,
+            None => return,
+        };
+
+        let recipient =
+            if self.is_transferable(transfer.token_id, &transfer.sender, &transfer.recipient) {
+                transfer.recipient
+            } else {
+                transfer.sender
+            };
+
+        let transaction_hash = get_step_hash(transfer.transaction_hash, 2);
+        let escrow_storage_id = self.get_or_create_storage_address(&escrow);
+        let recipient_storage_id = self.get_or_create_storage_address(&recipient);
+        let snapshot_id = self.get_snapshot_id(transfer.token_id);
+
+        let instructions = self
+            .instructions
+            .entry(transaction_hash)
+            .or_insert_with(|| {
+                vec![
+                    create_decrease_instruction(
+                        transaction_hash,
+                        &escrow_storage_id,
+                        transfer.token_id,
+                        snapshot_id,
+                        &escrow,
+                        &escrow,
+                        transfer.amount,
+                    ),
+                    create_increase_instruction(
+                        get_step_hash(transfer.transaction_hash, 3),
+                        &recipient_storage_id,
+                        transfer.token_id,
+                        snapshot_id,
+                        &recipient,
+                        transfer.amount,
+                    ),
+                ]
+            });
+
+        let mut result = execute_instructions(instructions).await;
+
+        // The failed transfer is refunded to the sender
+        if result == ExecutionResult::Aborted {
+            result = self.refund_scheduled_transfer(&transfer).await;
+        }
+
+        // The sender can still cancel the transfer to get the tokens back,
+        // but a failed compensation is retried to completion before that
+        if result != ExecutionResult::Success {
+            self.scheduled_transfers.insert(transfer_id, transfer);
+        }
+    }
+
+    async fn refund_scheduled_transfer(&mut self, transfer: &ScheduledTransfer) -> ExecutionResult {
+        let escrow = exec::program_id();
+        let transaction_hash = get_step_hash(transfer.transaction_hash, 4);
+        let escrow_storage_id = self.get_or_create_storage_address(&escrow);
+        let sender_storage_id = self.get_or_create_storage_address(&transfer.sender);
+        let snapshot_id = self.get_snapshot_id(transfer.token_id);
+
+        let instructions = self
+            .instructions
+            .entry(transaction_hash)
+            .or_insert_with(|| {
+                vec![
+                    create_decrease_instruction(
+                        transaction_hash,
+                        &escrow_storage_id,
+                        transfer.token_id,
+                        snapshot_id,
+                        &escrow,
+                        &escrow,
+                        transfer.amount,
+                    ),
+                    create_increase_instruction(
+                        get_step_hash(transfer.transaction_hash, 5),
+                        &sender_storage_id,
+                        transfer.token_id,
+                        snapshot_id,
+                        &transfer.sender,
+                        transfer.amount,
+                    ),
+                ]
+            });
+
+        execute_instructions(instructions).await
+    }
+
+    async fn cancel_scheduled_transfer(
+        &mut self,
+        transaction_hash: H256,
+        transfer_id: u64,
+        msg_source: &ActorId,
+    ) {
+        // The transfer is already removed, if the cancellation is retried
+        let mut cancelled = None;
+
+        if !self.instructions.contains_key(&transaction_hash) {
+            let is_sender = self
+                .scheduled_transfers
+                .get(&transfer_id)
+                .map_or(false, |transfer| transfer.sender == *msg_source);
+
+            if !is_sender {
+                self.transaction_status
+                    .insert(transaction_hash, TransactionStatus::Failure);
+                reply_err();
+                return;
+            }
+
+            let transfer = self
+                .scheduled_transfers
+                .remove(&transfer_id)
+                .expect("Transfer must exist.");
+
+            // Tokens are locked, while compensations of the failed execution
+            // and the failed refund aren't completed
+            for step in [2, 4] {
+                let execution_hash = get_step_hash(transfer.transaction_hash, step);
+                if let Some(instructions) = self.instructions.get_mut(&execution_hash) {
+                    if execute_instructions(instructions).await != ExecutionResult::Aborted {
+                        self.scheduled_transfers.insert(transfer_id, transfer);
+                        self.transaction_status
+                            .insert(transaction_hash, TransactionStatus::Failure);
+                        reply_err();
+                        return;
+                    }
+                }
+            }
+
+            let escrow = exec::program_id();
+            let escrow_storage_id = self.get_or_create_storage_address(&escrow);
+            let sender_storage_id = self.get_or_create_storage_address(&transfer.sender);
+            let snapshot_id = self.get_snapshot_id(transfer.token_id);
+
+            self.instructions.insert(
+                transaction_hash,
+                vec![
+                    create_decrease_instruction(
+                        transaction_hash,
+                        &escrow_storage_id,
+                        transfer.token_id,
+                        snapshot_id,
+                        &escrow,
+                        &escrow,
+                        transfer.amount,
+                    ),
+                    create_increase_instruction(
+                        get_step_hash(transaction_hash, 1),
+                        &sender_storage_id,
+                        transfer.token_id,
+                        snapshot_id,
+                        &transfer.sender,
+                        transfer.amount,
+                    ),
+                ],
+            );
+            cancelled = Some(transfer);
+        }
+
+        let instructions = self
+            .instructions
+            .get_mut(&transaction_hash)
+            .expect("Instructions must exist.");
+
+        match execute_instructions(instructions).await {
+            ExecutionResult::Success => {
+                self.transaction_status
+                    .insert(transaction_hash, TransactionStatus::Success);
+                reply_ok();
+            }
+            ExecutionResult::Aborted => {
+                if let Some(transfer) = cancelled {
+                    self.scheduled_transfers.insert(transfer_id, transfer);
+                }
+
+                self.transaction_status
+                    .insert(transaction_hash, TransactionStatus::Failure);
+                reply_err();
+            }
+            // The transaction stays in progress to be retried
+            ExecutionResult::AbortFailed => {}
+        }
+    }
+
+    async fn multicall(
+        &mut self,
+        transaction_hash: H256,
+        msg_source: &ActorId,
+        actions: Vec<LogicAction>,
+    ) {
+        // A retried transaction continues with the instructions and changes of the first attempt
+        if !self.multicalls.contains_key(&transaction_hash) {
+            // Approvals are compensated by restoring the previous ones,
+            // which are read before token ids are reserved
+            let mut approvals = Vec::new();
+            for action in &actions {
+                let (owner, account) = match action {
+                    LogicAction::Approve { account, .. } => (*msg_source, *account),
+                    LogicAction::Permit { owner, account, .. } => (*owner, *account),
+                    _ => continue,
+                };
+
+                let storage_id = self.get_or_create_storage_address(&owner);
+                match get_approval(&storage_id, &owner, &account).await {
+                    Ok(is_approved) => approvals.push(is_approved),
+                    Err(()) => {
+                        self.transaction_status
+                            .insert(transaction_hash, TransactionStatus::Failure);
+                        reply_err();
+                        return;
+                    }
+                }
+            }
+            let mut approvals = approvals.into_iter();
+
+            let mut changes = MulticallChanges {
+                token_nonce: self.token_nonce,
+                ..Default::default()
+            };
+            let mut instructions = Vec::new();
+            let mut replies = Vec::with_capacity(actions.len());
+
+            for action in actions {
+                match self.plan_multicall_action(
+                    transaction_hash,
+                    msg_source,
+                    action,
+                    &mut approvals,
+                    &mut changes,
+                    &mut instructions,
+                ) {
+                    Ok(reply) => replies.push(reply),
+                    Err(()) => {
+                        self.transaction_status
+                            .insert(transaction_hash, TransactionStatus::Failure);
+                        reply_err();
+                        return;
+                    }
+                }
+            }
+
+            // Token ids, NFT indexes and permit nonces are reserved before awaits,
+            // so concurrent transactions can't take them
+            self.token_nonce = changes.token_nonce;
+            for (token_id, (_, max_index)) in &changes.nft_indexes {
+                self.nft_max_index.insert(*token_id, *max_index);
+            }
+            for (owner, (_, nonce)) in &changes.permit_nonces {
+                self.permit_nonces.insert(*owner, *nonce);
+            }
+
+            self.instructions.insert(transaction_hash, instructions);
+            self.multicalls.insert(transaction_hash, (changes, replies));
+        }
+
+        let instructions = self
+            .instructions
+            .get_mut(&transaction_hash)
+            .expect("Instructions of the multicall must be stored.");
+
+        match execute_instructions(instructions).await {
+            ExecutionResult::Success => {
+                let (changes, replies) = self
+                    .multicalls
+                    .remove(&transaction_hash)
+                    .expect("Changes of the multicall must be stored.");
+                self.apply_multicall_changes(changes);
+
+                self.transaction_status
+                    .insert(transaction_hash, TransactionStatus::Success);
+                msg::reply(MTLogicEvent::Multicall(replies), 0)
+                    .expect("Error in a reply `MTLogicEvent::Multicall`.");
+            }
+            ExecutionResult::Aborted => {
+                let (changes, _) = self
+                    .multicalls
+                    .remove(&transaction_hash)
+                    .expect("Changes of the multicall must be stored.");
+                self.release_multicall_reservations(&changes);
+
+                self.transaction_status
+                    .insert(transaction_hash, TransactionStatus::Failure);
+                reply_err();
+            }
+            // The transaction stays in progress to be retried
+            ExecutionResult::AbortFailed => {}
+        }
+    }
+
+    /// Validates `action` against the state with `changes` of the previous actions
+    /// of the multicall, then records its instructions and changes.
+    fn plan_multicall_action(
+        &mut self,
+        transaction_hash: H256,
+        msg_source: &ActorId,
+        action: LogicAction,
+        approvals: &mut impl Iterator<Item = bool>,
+        changes: &mut MulticallChanges,
+        instructions: &mut Vec<Instruction>,
+    ) -> Result<MTLogicEvent, ()> {
+        match action {
+            LogicAction::Create {
+                initial_amount,
+                uri,
+                metadata,
+                is_nft,
+                is_soulbound,
+                is_edition,
+                royalty,
+            } => {
+                if royalty.map_or(false, |royalty| {
+                    royalty.basis_points > BASIS_POINTS_DENOMINATOR
+                }) {
+                    return Err(());
+                }
+
+                changes.token_nonce = changes.token_nonce.checked_add(1).ok_or(())?;
+                let token_id = Self::get_token_type(changes.token_nonce, is_nft);
+
+                if !is_nft {
+                    changes.mint(token_id, initial_amount)?;
+
+                    let to_storage_id = self.get_or_create_storage_address(msg_source);
+                    instructions.push(create_increase_instruction(
+                        get_step_hash(transaction_hash, instructions.len() as u32),
+                        &to_storage_id,
+                        token_id,
+                        self.get_snapshot_id(token_id),
+                        msg_source,
+                        initial_amount,
+                    ));
+                }
+
+                changes.new_tokens.push(NewToken {
+                    token_id,
+                    creator: *msg_source,
+                    uri,
+                    metadata,
+                    is_soulbound,
+                    is_edition: is_nft && is_edition,
+                    royalty,
+                });
+
+                Ok(MTLogicEvent::Created(token_id))
+            }
+            LogicAction::Transfer {
+                token_id,
+                sender,
+                recipient,
+                amount,
+            } => {
+                let is_soulbound = changes
+                    .new_token(Self::get_nft_base_type(token_id))
+                    .map_or(false, |token| token.is_soulbound);
+
+                if !self.has_balances(token_id)
+                    || is_soulbound
+                    || !self.is_transferable(token_id, &sender, &recipient)
+                {
+                    return Err(());
+                }
+
+                let (treasury, fee) = self
+                    .transfer_fees
+                    .get(&token_id)
+                    .map(|transfer_fee| (transfer_fee.treasury, transfer_fee.amount(amount)))
+                    .unwrap_or_default();
+
+                if fee > amount {
+                    return Err(());
+                }
+
+                let sender_storage_id = self.get_or_create_storage_address(&sender);
+                instructions.push(create_decrease_instruction(
+                    get_step_hash(transaction_hash, instructions.len() as u32),
+                    &sender_storage_id,
+                    token_id,
+                    self.get_snapshot_id(token_id),
+                    msg_source,
+                    &sender,
+                    amount,
+                ));
+
+                let recipient_storage_id = self.get_or_create_storage_address(&recipient);
+                instructions.push(create_increase_instruction(
+                    get_step_hash(transaction_hash, instructions.len() as u32),
+                    &recipient_storage_id,
+                    token_id,
+                    self.get_snapshot_id(token_id),
+                    &recipient,
+                    amount - fee,
+                ));
+
+                if fee != 0 {
+                    let treasury_storage_id = self.get_or_create_storage_address(&treasury);
+                    instructions.push(create_increase_instruction(
+                        get_step_hash(transaction_hash, instructions.len() as u32),
+                        &treasury_storage_id,
+                        token_id,
+                        self.get_snapshot_id(token_id),
+                        &treasury,
+                        fee,
+                    ));
+                }
+
+                Ok(MTLogicEvent::Ok)
+            }
+            LogicAction::MintBatchFT {
+                token_id,
+                to,
+                amounts,
+            } => {
+                if to.len() != amounts.len()
+                    || msg_source.is_zero()
+                    || !Self::is_ft(token_id)
+                    || token_id == NATIVE_TOKEN_ID
+                    || self.fractional_tokens.contains_key(&token_id)
+                    || to
+                        .iter()
+                        .any(|to| self.is_frozen(token_id, to) || !self.is_allowed(token_id, to))
+                {
+                    return Err(());
+                }
+
+                let mut token_total_supply = self.multicall_total_supply(changes, token_id)?;
+
+                for (to, amount) in to.iter().zip(amounts) {
+                    token_total_supply = token_total_supply.checked_add(amount).ok_or(())?;
+                    changes.mint(token_id, amount)?;
+
+                    let to_storage_id = self.get_or_create_storage_address(to);
+                    instructions.push(create_increase_instruction(
+                        get_step_hash(transaction_hash, instructions.len() as u32),
+                        &to_storage_id,
+                        token_id,
+                        self.get_snapshot_id(token_id),
+                        to,
+                        amount,
+                    ));
+                }
+
+                Ok(MTLogicEvent::Ok)
+            }
+            LogicAction::BurnBatchFT {
+                token_id,
+                burn_from,
+                amounts,
+            } => {
+                if burn_from.len() != amounts.len()
+                    || msg_source.is_zero()
+                    || !self.has_balances(token_id)
+                    || token_id == NATIVE_TOKEN_ID
+                    || burn_from.iter().any(|from| self.is_frozen(token_id, from))
+                {
+                    return Err(());
+                }
+
+                let mut token_total_supply = self.multicall_total_supply(changes, token_id)?;
+
+                for (from, amount) in burn_from.iter().zip(amounts) {
+                    token_total_supply = token_total_supply.checked_sub(amount).ok_or(())?;
+                    changes.burn(token_id, amount)?;
+                    if self.is_edition(token_id) {
+                        changes.burn(Self::get_nft_base_type(token_id), amount)?;
+                    }
+
+                    // Storages check that `msg_source` is `from` or approved by it
+                    let from_storage_id = self.get_or_create_storage_address(from);
+                    instructions.push(create_decrease_instruction(
+                        get_step_hash(transaction_hash, instructions.len() as u32),
+                        &from_storage_id,
+                        token_id,
+                        self.get_snapshot_id(token_id),
+                        msg_source,
+                        from,
+                        amount,
+                    ));
+                }
+
+                Ok(MTLogicEvent::Ok)
+            }
+            LogicAction::MintBatchNFT {
+                token_id,
+                to,
+                metadata,
+            } => {
+                let new_token = changes.new_token(token_id);
+                let is_created = new_token.is_some() || self.token_creators.contains_key(&token_id);
+                let is_edition = new_token.map_or(false, |token| token.is_edition)
+                    || self.edition_tokens.contains(&token_id);
+
+                if !Self::is_nft_base_type(token_id)
+                    || !is_created
+                    || is_edition
+                    || (!metadata.is_empty() && metadata.len() != to.len())
+                    || to
+                        .iter()
+                        .any(|to| self.is_frozen(token_id, to) || !self.is_allowed(token_id, to))
+                {
+                    return Err(());
+                }
+
+                let initial_max_index = self
+                    .nft_max_index
+                    .get(&token_id)
+                    .copied()
+                    .unwrap_or_default();
+                let (_, max_index) = changes
+                    .nft_indexes
+                    .entry(token_id)
+                    .or_insert((initial_max_index, initial_max_index));
+                let ids: Vec<TokenId> = (1..=to.len())
+                    .map(|i| token_id | (*max_index + i as TokenId))
+                    .collect();
+                *max_index = max_index.checked_add(to.len() as TokenId).ok_or(())?;
+
+                changes.mint(token_id, to.len() as u128)?;
+
+                changes.nft_owners.extend(ids.iter().copied().zip(to));
+                changes.nft_metadata.extend(
+                    ids.iter()
+                        .zip(metadata)
+                        .filter_map(|(id, metadata)| metadata.map(|metadata| (*id, metadata))),
+                );
+
+                Ok(MTLogicEvent::NFTMinted(ids))
+            }
+            LogicAction::Approve {
+                account,
+                is_approved,
+            } => {
+                let storage_id = self.get_or_create_storage_address(msg_source);
+                instructions.push(create_approve_instruction(
+                    get_step_hash(transaction_hash, instructions.len() as u32),
+                    &storage_id,
+                    msg_source,
+                    &account,
+                    is_approved,
+                    approvals.next().ok_or(())?,
+                ));
+
+                Ok(MTLogicEvent::Ok)
+            }
+            LogicAction::Permit {
+                owner,
+                account,
+                is_approved,
+                deadline,
+                signature,
+            } => {
+                let initial_nonce = self.permit_nonces.get(&owner).copied().unwrap_or(0);
+                let (_, nonce) = changes
+                    .permit_nonces
+                    .entry(owner)
+                    .or_insert((initial_nonce, initial_nonce));
+                let data = permit_data(
+                    &self.mtoken_id,
+                    &owner,
+                    &account,
+                    is_approved,
+                    *nonce,
+                    deadline,
+                );
+
+                if exec::block_height() > deadline || !verify_signature(&owner, &data, &signature) {
+                    return Err(());
+                }
+
+                *nonce = nonce.checked_add(1).ok_or(())?;
 
-            let from_storage_id = self.get_or_create_storage_address(from);
-            let mut decrease_instruction = create_decrease_instruction(
-                transaction_hash,
-                &from_storage_id,
-                token_id,
-                msg_source,
-                from,
-                amount,
-            );
+                let storage_id = self.get_or_create_storage_address(&owner);
+                instructions.push(create_approve_instruction(
+                    get_step_hash(transaction_hash, instructions.len() as u32),
+                    &storage_id,
+                    &owner,
+                    &account,
+                    is_approved,
+                    approvals.next().ok_or(())?,
+                ));
 
-            let token_total_supply = self
-                .token_total_supply
-                .get_mut(&token_id)
-                .expect("Unable to locate token.");
-            let new_token_total_supply = token_total_supply
-                .checked_sub(amount)
+                Ok(MTLogicEvent::Ok)
+            }
+            // Other actions can't be compensated or don't change balances
+            _ => Err(()),
+        }
+    }
+
+    /// Returns the total supply of `token_id` with `changes` of the previous actions of the multicall.
+    fn multicall_total_supply(
+        &self,
+        changes: &MulticallChanges,
+        token_id: TokenId,
+    ) -> Result<u128, ()> {
+        let token_total_supply = self.token_total_supply.get(&token_id).copied();
+
+        match changes.token_supply_changes.get(&token_id) {
+            Some((minted, burned)) => token_total_supply
+                .unwrap_or_default()
+                .checked_add(*minted)
+                .and_then(|total_supply| total_supply.checked_sub(*burned))
+                .ok_or(()),
+            None => token_total_supply.ok_or(()),
+        }
+    }
+
+    fn apply_multicall_changes(&mut self, changes: MulticallChanges) {
+        for token in changes.new_tokens {
+            self.insert_token(token);
+        }
+
+        // Supplies are changed by the minted and burned amounts,
+        // since other transactions may change them meanwhile
+        for (token_id, (minted, burned)) in changes.token_supply_changes {
+            let token_total_supply = self.token_total_supply.entry(token_id).or_default();
+            *token_total_supply = token_total_supply
+                .checked_add(minted)
+                .and_then(|total_supply| total_supply.checked_sub(burned))
                 .expect("Math overflow!");
+        }
 
-            if decrease_instruction.start().await.is_err() {
-                self.transaction_status
-                    .insert(transaction_hash, TransactionStatus::Failure);
-                reply_err();
-                return;
-            }
+        for (token_id, owner) in changes.nft_owners {
+            self.set_nft_owner(token_id, Some(owner));
+        }
 
-            *token_total_supply = new_token_total_supply;
+        self.nft_metadata.extend(changes.nft_metadata);
+    }
+
+    /// Releases token nonces, NFT indexes and permit nonces reserved by the compensated multicall,
+    /// unless later ones are taken already.
+    fn release_multicall_reservations(&mut self, changes: &MulticallChanges) {
+        if self.token_nonce == changes.token_nonce {
+            self.token_nonce = changes.token_nonce - changes.new_tokens.len() as TokenId;
         }
 
-        self.transaction_status
-            .insert(transaction_hash, TransactionStatus::Success);
-        reply_ok();
+        for (token_id, (initial_max_index, max_index)) in &changes.nft_indexes {
+            if self.nft_max_index.get(token_id) == Some(max_index) {
+                self.nft_max_index.insert(*token_id, *initial_max_index);
+            }
+        }
+
+        for (owner, (initial_nonce, nonce)) in &changes.permit_nonces {
+            if self.permit_nonces.get(owner) == Some(nonce) {
+                self.permit_nonces.insert(*owner, *initial_nonce);
+            }
+        }
     }
 
-    async fn burn_nft(
-        &mut self,
-        transaction_hash: H256,
-        token_id: TokenId,
-        msg_source: &ActorId,
-        from: &ActorId,
-    ) {
-        self.transaction_status
-            .insert(transaction_hash, TransactionStatus::InProgress);
+    fn snapshot(&mut self, transaction_hash: H256, token_id: TokenId, msg_source: &ActorId) {
+        let is_allowed =
+            *msg_source == self.admin || self.token_creators.get(&token_id) == Some(msg_source);
 
-        if !Self::is_nft(token_id) {
+        if !is_allowed || !self.token_creators.contains_key(&token_id) || Self::is_nft(token_id) {
             self.transaction_status
                 .insert(transaction_hash, TransactionStatus::Failure);
             reply_err();
             return;
         }
 
-        // 1. Check that `msg_source` is eq to `from` or approved
-        if !self.is_approved(from, msg_source).await {
-            self.transaction_status
-                .insert(transaction_hash, TransactionStatus::Failure);
-            reply_err();
-            return;
+        let snapshot_id = self
+            .get_snapshot_id(token_id)
+            .checked_add(1)
+            .expect("Math overflow!");
+
+        // The snapshot is taken here, since every balance change carries the latest snapshot id.
+        // Storages are notified without waiting for replies, so they don't switch one by one,
+        // and storages created later get the snapshot id on initialization
+        self.token_snapshots.insert(token_id, snapshot_id);
+        for storage_id in self.id_to_storage.values() {
+            snapshot(storage_id, transaction_hash, token_id, snapshot_id);
         }
 
-        // 2. Check that `token_id` nft owner is `from`
-        if let Some(nft_owner) = self.nft_owners.get(&token_id) {
-            if nft_owner != from {
-                // Error, invalid owner
-                self.transaction_status
-                    .insert(transaction_hash, TransactionStatus::Failure);
+        self.transaction_status
+            .insert(transaction_hash, TransactionStatus::Success);
+        msg::reply(
+            MTLogicEvent::SnapshotTaken {
+                token_id,
+                snapshot_id,
+            },
+            0,
+        )
+        .expect("Error in a reply `MTLogicEvent::SnapshotTaken`.");
+    }
+
+    async fn change_native_balance(
+        &mut self,
+        transaction_hash: H256,
+        account: &ActorId,
+        amount: u128,
+        is_deposit: bool,
+    ) {
+        self.assert_main_contract();
+
+        match self.transaction_status.get(&transaction_hash) {
+            Some(TransactionStatus::Success) => {
+                reply_ok();
+                return;
+            }
+            Some(TransactionStatus::Failure) => {
                 reply_err();
                 return;
             }
+            _ => {}
+        }
+
+        self.transaction_status
+            .insert(transaction_hash, TransactionStatus::InProgress);
+
+        let storage_id = self.get_or_create_storage_address(account);
+        let mut instruction = if is_deposit {
+            create_increase_instruction(
+                transaction_hash,
+                &storage_id,
+                NATIVE_TOKEN_ID,
+                self.get_snapshot_id(NATIVE_TOKEN_ID),
+                account,
+                amount,
+            )
         } else {
-            // Error, token not found
+            create_decrease_instruction(
+                transaction_hash,
+                &storage_id,
+                NATIVE_TOKEN_ID,
+                self.get_snapshot_id(NATIVE_TOKEN_ID),
+                account,
+                account,
+                amount,
+            )
+        };
+
+        if instruction.start().await.is_err() {
             self.transaction_status
                 .insert(transaction_hash, TransactionStatus::Failure);
             reply_err();
             return;
         }
 
-        // 3. Remove `token_id` nft
-        self.nft_owners.remove(&token_id);
+        let total_supply = self.token_total_supply.entry(NATIVE_TOKEN_ID).or_default();
+        *total_supply = if is_deposit {
+            total_supply.checked_add(amount)
+        } else {
+            total_supply.checked_sub(amount)
+        }
+        .expect("Math overflow!");
 
         self.transaction_status
             .insert(transaction_hash, TransactionStatus::Success);
@@ -570,7 +2810,14 @@ impl MTLogic {
         } else {
             let (_message_id, address) = ProgramGenerator::create_program_with_gas(
                 self.storage_code_hash.into(),
-                "",
+                InitMTStorage {
+                    snapshots: self
+                        .token_snapshots
+                        .iter()
+                        .map(|(token_id, snapshot_id)| (*token_id, *snapshot_id))
+                        .collect(),
+                }
+                .encode(),
                 GAS_STORAGE_CREATION,
                 0,
             )
@@ -580,8 +2827,37 @@ impl MTLogic {
         }
     }
 
+    fn get_storage_address(&self, address: &ActorId) -> Option<ActorId> {
+        let encoded = hex::encode(address.as_ref());
+        let id: String = encoded.chars().next().expect("Can't be None.").to_string();
+
+        self.id_to_storage.get(&id).copied()
+    }
+
+    fn set_nft_owner(&mut self, token_id: TokenId, owner: Option<ActorId>) {
+        let previous_owner = match owner {
+            Some(owner) => self.nft_owners.insert(token_id, owner),
+            None => self.nft_owners.remove(&token_id),
+        };
+
+        if let Some(previous_owner) = previous_owner {
+            if let Some(nfts) = self.account_nfts.get_mut(&previous_owner) {
+                nfts.remove(&token_id);
+            }
+        }
+
+        if let Some(owner) = owner {
+            self.account_nfts.entry(owner).or_default().insert(token_id);
+        }
+    }
+
     async fn get_balance(&self, token_id: TokenId, account: &ActorId) {
-        if Self::is_nft(token_id) {
+        if Self::is_nft(token_id) && !self.is_known_nft_item(token_id) {
+            reply_err();
+            return;
+        }
+
+        if !self.has_balances(token_id) {
             let balance = match self.nft_owners.get(&token_id) {
                 Some(owner) if owner == account => 1,
                 Some(_) => 0,
@@ -597,35 +2873,195 @@ impl MTLogic {
         let id: String = encoded.chars().next().expect("Can't be None.").to_string();
 
         if let Some(storage_id) = self.id_to_storage.get(&id) {
-            let balance = get_balance(storage_id, token_id, account)
-                .await
-                .unwrap_or(0);
-
-            msg::reply(MTLogicEvent::Balance(balance), 0)
-                .expect("Error in a reply `MTLogicEvent::Balance`.");
+            match get_balance(storage_id, token_id, account).await {
+                Ok(balance) => {
+                    msg::reply(MTLogicEvent::Balance(balance), 0)
+                        .expect("Error in a reply `MTLogicEvent::Balance`.");
+                }
+                Err(()) => reply_err(),
+            }
         } else {
             msg::reply(MTLogicEvent::Balance(0), 0)
                 .expect("Error in a reply `MTLogicEvent::Balance`.");
         }
     }
 
-    async fn is_approved(&self, from: &ActorId, to: &ActorId) -> bool {
+    async fn balance_of_at(&self, token_id: TokenId, account: &ActorId, snapshot_id: u64) {
+        let current_snapshot = self.get_snapshot_id(token_id);
+        if snapshot_id == 0 || snapshot_id > current_snapshot {
+            reply_err();
+            return;
+        }
+
+        let balance = match self.get_storage_address(account) {
+            Some(storage_id) => balance_of_at(&storage_id, token_id, account, snapshot_id).await,
+            None => Ok(0),
+        };
+
+        match balance {
+            Ok(balance) => {
+                msg::reply(MTLogicEvent::Balance(balance), 0)
+                    .expect("Error in a reply `MTLogicEvent::Balance`.");
+            }
+            Err(()) => reply_err(),
+        }
+    }
+
+    async fn tokens_of(&self, account: &ActorId, offset: u32, limit: u32) {
+        let (mut tokens, total) = match self.get_storage_address(account) {
+            Some(storage_id) => match tokens_of(&storage_id, account, offset, limit).await {
+                Ok(result) => result,
+                Err(()) => {
+                    reply_err();
+                    return;
+                }
+            },
+            None => (vec![], 0),
+        };
+
+        // Fungible tokens go first, then owned NFT items follow
+        let remaining = (limit as usize).saturating_sub(tokens.len());
+        if remaining > 0 {
+            let mut nfts: Vec<TokenId> = self
+                .account_nfts
+                .get(account)
+                .map(|nfts| nfts.iter().copied().collect())
+                .unwrap_or_default();
+            nfts.sort_unstable();
+
+            tokens.extend(
+                nfts.into_iter()
+                    .skip(offset.saturating_sub(total) as usize)
+                    .take(remaining),
+            );
+        }
+
+        msg::reply(MTLogicEvent::TokensOf(tokens), 0)
+            .expect("Error in a reply `MTLogicEvent::TokensOf`.");
+    }
+
+    async fn holders_of(&self, token_id: TokenId, offset: u32, limit: u32) {
+        let mut storages: Vec<(&String, &ActorId)> = self.id_to_storage.iter().collect();
+        storages.sort_unstable_by(|a, b| a.0.cmp(b.0));
+
+        let mut holders = Vec::new();
+        let mut total: u32 = 0;
+        let mut skip = offset;
+
+        // Every storage is queried to sum up the total number of holders
+        for (_, storage_id) in storages {
+            let remaining = (limit as usize).saturating_sub(holders.len()) as u32;
+            match holders_of(storage_id, token_id, skip, remaining).await {
+                Ok((page, storage_total)) => {
+                    skip = skip.saturating_sub(storage_total);
+                    total = total.saturating_add(storage_total);
+                    holders.extend(page);
+                }
+                Err(()) => {
+                    reply_err();
+                    return;
+                }
+            }
+        }
+
+        msg::reply(MTLogicEvent::HoldersOf { holders, total }, 0)
+            .expect("Error in a reply `MTLogicEvent::HoldersOf`.");
+    }
+
+    async fn is_approved(&self, from: &ActorId, to: &ActorId) -> Result<bool, ()> {
         let encoded = hex::encode(from.as_ref());
         let id: String = encoded.chars().next().expect("Can't be None.").to_string();
 
         if let Some(storage_id) = self.id_to_storage.get(&id) {
-            get_approval(storage_id, from, to).await.unwrap_or(false)
+            get_approval(storage_id, from, to).await
         } else {
-            from == to
+            Ok(from == to)
         }
     }
 
     async fn get_approval(&self, account: &ActorId, approval_target: &ActorId) {
-        msg::reply(
-            MTLogicEvent::Approval(self.is_approved(account, approval_target).await),
-            0,
-        )
-        .expect("Error in a reply `MTLogicEvent::Approval`.");
+        match self.is_approved(account, approval_target).await {
+            Ok(approval) => {
+                msg::reply(MTLogicEvent::Approval(approval), 0)
+                    .expect("Error in a reply `MTLogicEvent::Approval`.");
+            }
+            Err(()) => reply_err(),
+        }
+    }
+
+    fn get_token_metadata(&self, token_id: TokenId) {
+        let token_id = if Self::is_nft(token_id) {
+            Self::get_nft_base_type(token_id)
+        } else {
+            token_id
+        };
+
+        match self.token_metadata.get(&token_id) {
+            Some(metadata) => {
+                msg::reply(
+                    MTLogicEvent::TokenMetadata {
+                        metadata: metadata.clone(),
+                        uri: self.get_token_uri(token_id),
+                        is_frozen: self.frozen_metadata.contains(&token_id),
+                    },
+                    0,
+                )
+                .expect("Error in a reply `MTLogicEvent::TokenMetadata`.");
+            }
+            None => reply_err(),
+        }
+    }
+
+    fn royalty_info(&self, token_id: TokenId, sale_price: u128) {
+        let base_type = Self::get_nft_base_type(token_id);
+        let is_known = if Self::is_nft_item(token_id) {
+            self.is_known_nft_item(token_id)
+        } else {
+            self.token_creators.contains_key(&token_id)
+        };
+
+        if !is_known {
+            reply_err();
+            return;
+        }
+
+        let (receiver, amount) = self
+            .token_royalties
+            .get(&token_id)
+            .or_else(|| self.token_royalties.get(&base_type))
+            .map(|royalty| (royalty.receiver, royalty.amount(sale_price)))
+            .unwrap_or_default();
+
+        msg::reply(MTLogicEvent::RoyaltyInfo { receiver, amount }, 0)
+            .expect("Error in a reply `MTLogicEvent::RoyaltyInfo`.");
+    }
+
+    fn get_token_uris(&self, after: Option<TokenId>, limit: u32) {
+        let token_uris = paginate(&self.token_uris, after, limit);
+
+        msg::reply(MTLogicEvent::TokenUris(token_uris), 0)
+            .expect("Error in a reply `MTLogicEvent::TokenUris`.");
+    }
+
+    fn get_tokens_total_supply(&self, after: Option<TokenId>, limit: u32) {
+        let token_total_supply = paginate(&self.token_total_supply, after, limit);
+
+        msg::reply(MTLogicEvent::TokenTotalSupply(token_total_supply), 0)
+            .expect("Error in a reply `MTLogicEvent::TokenTotalSupply`.");
+    }
+
+    fn get_token_creators(&self, after: Option<TokenId>, limit: u32) {
+        let token_creators = paginate(&self.token_creators, after, limit);
+
+        msg::reply(MTLogicEvent::TokenCreators(token_creators), 0)
+            .expect("Error in a reply `MTLogicEvent::TokenCreators`.");
+    }
+
+    fn get_instructions(&self, after: Option<H256>, limit: u32) {
+        let instructions = paginate(&self.instructions, after, limit);
+
+        msg::reply(MTLogicEvent::Instructions(instructions), 0)
+            .expect("Error in a reply `MTLogicEvent::Instructions`.");
     }
 
     fn is_ft(token_id: TokenId) -> bool {
@@ -636,27 +3072,90 @@ impl MTLogic {
         token_id & NFT_BIT == NFT_BIT
     }
 
-    #[allow(unused)]
+    fn is_edition(&self, token_id: TokenId) -> bool {
+        Self::is_nft(token_id)
+            && self
+                .edition_tokens
+                .contains(&Self::get_nft_base_type(token_id))
+    }
+
+    /// Returns whether `token_id` balances are stored in storages instead of `nft_owners`.
+    fn has_balances(&self, token_id: TokenId) -> bool {
+        Self::is_ft(token_id) || (self.is_edition(token_id) && self.is_known_nft_item(token_id))
+    }
+
     fn get_nft_index(token_id: TokenId) -> TokenId {
         token_id & NFT_INDEX_MASK
     }
 
-    #[allow(unused)]
+    fn is_transferable(&self, token_id: TokenId, sender: &ActorId, recipient: &ActorId) -> bool {
+        !self
+            .soulbound_tokens
+            .contains(&Self::get_nft_base_type(token_id))
+            && !self.is_frozen(token_id, sender)
+            && !self.is_frozen(token_id, recipient)
+            && self.is_allowed(token_id, sender)
+            && self.is_allowed(token_id, recipient)
+    }
+
+    fn is_frozen(&self, token_id: TokenId, account: &ActorId) -> bool {
+        let token_id = Self::get_nft_base_type(token_id);
+
+        self.frozen_tokens.contains(&token_id)
+            || self
+                .frozen_accounts
+                .get(&token_id)
+                .map_or(false, |accounts| accounts.contains(account))
+    }
+
+    fn is_allowed(&self, token_id: TokenId, account: &ActorId) -> bool {
+        let token_id = Self::get_nft_base_type(token_id);
+
+        !self.allowlist_tokens.contains(&token_id)
+            || self
+                .token_allowlists
+                .get(&token_id)
+                .map_or(false, |accounts| accounts.contains(account))
+    }
+
+    fn get_token_type(nonce: TokenId, is_nft: bool) -> TokenId {
+        // Store the type in the upper 64 bits
+        // Before: 0 0 0 0 0 0 1
+        // After:  0 0 0 1 0 0 0
+        let token_type = nonce << (mem::size_of::<TokenId>() * 8 / 2);
+
+        // Set a flag, if this is an NFT
+        if is_nft {
+            token_type | NFT_BIT
+        } else {
+            token_type
+        }
+    }
+
     fn get_nft_base_type(token_id: TokenId) -> TokenId {
         token_id & NFT_TYPE_MASK
     }
 
-    #[allow(unused)]
     fn is_nft_base_type(token_id: TokenId) -> bool {
         (token_id & NFT_BIT == NFT_BIT) && (token_id & NFT_INDEX_MASK == 0)
     }
 
-    #[allow(unused)]
     fn is_nft_item(token_id: TokenId) -> bool {
         (token_id & NFT_BIT == NFT_BIT) && (token_id & NFT_INDEX_MASK != 0)
     }
 
-    #[allow(unused)]
+    fn is_known_nft_item(&self, token_id: TokenId) -> bool {
+        let base_type = Self::get_nft_base_type(token_id);
+
+        Self::is_nft_item(token_id)
+            && self.token_creators.contains_key(&base_type)
+            && Self::get_nft_index(token_id) <= *self.nft_max_index.get(&base_type).unwrap_or(&0)
+    }
+
+    fn get_snapshot_id(&self, token_id: TokenId) -> u64 {
+        self.token_snapshots.get(&token_id).copied().unwrap_or(0)
+    }
+
     fn get_token_uri(&self, token_id: TokenId) -> String {
         self.token_uris
             .get(&token_id)
@@ -723,6 +3222,27 @@ async fn main() {
             account,
             payload,
         } => logic.message(transaction_hash, &account, &payload).await,
+        MTLogicAction::Deposit {
+            transaction_hash,
+            account,
+            amount,
+        } => {
+            logic
+                .change_native_balance(transaction_hash, &account, amount, true)
+                .await
+        }
+        MTLogicAction::Withdraw {
+            transaction_hash,
+            account,
+            amount,
+        } => {
+            logic
+                .change_native_balance(transaction_hash, &account, amount, false)
+                .await
+        }
+        MTLogicAction::ExecuteScheduledTransfer(transfer_id) => {
+            logic.execute_scheduled_transfer(transfer_id).await
+        }
         MTLogicAction::GetBalance { token_id, account } => {
             logic.get_balance(token_id, &account).await
         }
@@ -730,6 +3250,32 @@ async fn main() {
             account,
             approval_target,
         } => logic.get_approval(&account, &approval_target).await,
+        MTLogicAction::BalanceOfAt {
+            token_id,
+            account,
+            snapshot_id,
+        } => logic.balance_of_at(token_id, &account, snapshot_id).await,
+        MTLogicAction::TokensOf {
+            account,
+            offset,
+            limit,
+        } => logic.tokens_of(&account, offset, limit).await,
+        MTLogicAction::HoldersOf {
+            token_id,
+            offset,
+            limit,
+        } => logic.holders_of(token_id, offset, limit).await,
+        MTLogicAction::GetTokenMetadata { token_id } => logic.get_token_metadata(token_id),
+        MTLogicAction::RoyaltyInfo {
+            token_id,
+            sale_price,
+        } => logic.royalty_info(token_id, sale_price),
+        MTLogicAction::GetTokenUris { after, limit } => logic.get_token_uris(after, limit),
+        MTLogicAction::GetTokenTotalSupply { after, limit } => {
+            logic.get_tokens_total_supply(after, limit)
+        }
+        MTLogicAction::GetTokenCreators { after, limit } => logic.get_token_creators(after, limit),
+        MTLogicAction::GetInstructions { after, limit } => logic.get_instructions(after, limit),
         MTLogicAction::UpdateStorageCodeHash(storage_code_hash) => {
             logic.update_storage_hash(storage_code_hash)
         }
@@ -772,6 +3318,69 @@ extern "C" fn state() {
             .map(|(a, b)| (*a, *b))
             .collect(),
         token_creators: logic.token_creators.iter().map(|(a, b)| (*a, *b)).collect(),
+        token_metadata: logic
+            .token_metadata
+            .iter()
+            .map(|(a, b)| (*a, b.clone()))
+            .collect(),
+        frozen_metadata: logic.frozen_metadata.iter().copied().collect(),
+        nft_max_index: logic.nft_max_index.iter().map(|(a, b)| (*a, *b)).collect(),
+        nft_owners: logic.nft_owners.iter().map(|(a, b)| (*a, *b)).collect(),
+        nft_metadata: logic
+            .nft_metadata
+            .iter()
+            .map(|(a, b)| (*a, b.clone()))
+            .collect(),
+        account_nfts: logic
+            .account_nfts
+            .iter()
+            .map(|(a, b)| (*a, b.iter().copied().collect()))
+            .collect(),
+        token_snapshots: logic
+            .token_snapshots
+            .iter()
+            .map(|(key, value)| (*key, *value))
+            .collect(),
+        frozen_tokens: logic.frozen_tokens.iter().copied().collect(),
+        frozen_accounts: logic
+            .frozen_accounts
+            .iter()
+            .map(|(a, b)| (*a, b.iter().copied().collect()))
+            .collect(),
+        allowlist_tokens: logic.allowlist_tokens.iter().copied().collect(),
+        soulbound_tokens: logic.soulbound_tokens.iter().copied().collect(),
+        edition_tokens: logic.edition_tokens.iter().copied().collect(),
+        edition_mints: logic.edition_mints.iter().map(|(a, b)| (*a, *b)).collect(),
+        token_royalties: logic
+            .token_royalties
+            .iter()
+            .map(|(a, b)| (*a, *b))
+            .collect(),
+        transfer_fees: logic.transfer_fees.iter().map(|(a, b)| (*a, *b)).collect(),
+        swap_nonce: logic.swap_nonce,
+        fractional_tokens: logic
+            .fractional_tokens
+            .iter()
+            .map(|(a, b)| (*a, *b))
+            .collect(),
+        permit_nonces: logic.permit_nonces.iter().map(|(a, b)| (*a, *b)).collect(),
+        scheduled_transfer_nonce: logic.scheduled_transfer_nonce,
+        scheduled_transfers: logic
+            .scheduled_transfers
+            .iter()
+            .map(|(a, b)| (*a, b.clone()))
+            .collect(),
+        swaps: logic.swaps.iter().map(|(a, b)| (*a, b.clone())).collect(),
+        settling_swaps: logic
+            .settling_swaps
+            .iter()
+            .map(|(a, b)| (*a, b.swap_id))
+            .collect(),
+        token_allowlists: logic
+            .token_allowlists
+            .iter()
+            .map(|(a, b)| (*a, b.iter().copied().collect()))
+            .collect(),
     };
 
     msg::reply(logic_state, 0).expect("Failed to share state.");
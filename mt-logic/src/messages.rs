@@ -1,5 +1,5 @@
 use gstd::{msg, prelude::*, ActorId};
-use mt_storage_io::{MTStorageAction, MTStorageEvent};
+use mt_storage_io::{MTStorageAction, MTStorageEvent, TokenId, VestingSchedule};
 use primitive_types::H256;
 
 pub async fn get_balance(
@@ -52,10 +52,12 @@ pub async fn get_approval(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn transfer(
     storage_id: &ActorId,
     transaction_hash: H256,
     token_id: u128,
+    snapshot_id: u64,
     msg_source: &ActorId,
     sender: &ActorId,
     recipient: &ActorId,
@@ -66,6 +68,7 @@ pub async fn transfer(
         MTStorageAction::Transfer {
             transaction_hash,
             token_id,
+            snapshot_id,
             msg_source: *msg_source,
             sender: *sender,
             recipient: *recipient,
@@ -119,6 +122,7 @@ pub async fn increase_balance(
     transaction_hash: H256,
     storage_id: &ActorId,
     token_id: u128,
+    snapshot_id: u64,
     account: &ActorId,
     amount: u128,
 ) -> Result<(), ()> {
@@ -127,6 +131,7 @@ pub async fn increase_balance(
         MTStorageAction::IncreaseBalance {
             transaction_hash,
             token_id,
+            snapshot_id,
             account: *account,
             amount,
         },
@@ -149,6 +154,7 @@ pub async fn decrease_balance(
     transaction_hash: H256,
     storage_id: &ActorId,
     token_id: u128,
+    snapshot_id: u64,
     msg_source: &ActorId,
     account: &ActorId,
     amount: u128,
@@ -158,6 +164,7 @@ pub async fn decrease_balance(
         MTStorageAction::DecreaseBalance {
             transaction_hash,
             token_id,
+            snapshot_id,
             msg_source: *msg_source,
             account: *account,
             amount,
@@ -175,3 +182,128 @@ pub async fn decrease_balance(
         Err(_) => Err(()),
     }
 }
+
+pub async fn tokens_of(
+    storage_id: &ActorId,
+    account: &ActorId,
+    offset: u32,
+    limit: u32,
+) -> Result<(Vec<TokenId>, u32), ()> {
+    let result = msg::send_for_reply_as::<_, MTStorageEvent>(
+        *storage_id,
+        MTStorageAction::TokensOf {
+            account: *account,
+            offset,
+            limit,
+        },
+        0,
+    )
+    .expect("Error in sending a message `MTStorageAction::TokensOf`.")
+    .await;
+
+    match result {
+        Ok(storage_event) => match storage_event {
+            MTStorageEvent::TokensOf { tokens, total } => Ok((tokens, total)),
+            _ => Err(()),
+        },
+        Err(_) => Err(()),
+    }
+}
+
+pub async fn holders_of(
+    storage_id: &ActorId,
+    token_id: TokenId,
+    offset: u32,
+    limit: u32,
+) -> Result<(Vec<(ActorId, u128)>, u32), ()> {
+    let result = msg::send_for_reply_as::<_, MTStorageEvent>(
+        *storage_id,
+        MTStorageAction::HoldersOf {
+            token_id,
+            offset,
+            limit,
+        },
+        0,
+    )
+    .expect("Error in sending a message `MTStorageAction::HoldersOf`.")
+    .await;
+
+    match result {
+        Ok(storage_event) => match storage_event {
+            MTStorageEvent::HoldersOf { holders, total } => Ok((holders, total)),
+            _ => Err(()),
+        },
+        Err(_) => Err(()),
+    }
+}
+
+pub async fn balance_of_at(
+    storage_id: &ActorId,
+    token_id: TokenId,
+    account: &ActorId,
+    snapshot_id: u64,
+) -> Result<u128, ()> {
+    let result = msg::send_for_reply_as::<_, MTStorageEvent>(
+        *storage_id,
+        MTStorageAction::BalanceOfAt {
+            token_id,
+            account: *account,
+            snapshot_id,
+        },
+        0,
+    )
+    .expect("Error in sending a message `MTStorageAction::BalanceOfAt`.")
+    .await;
+
+    match result {
+        Ok(storage_event) => match storage_event {
+            MTStorageEvent::Balance(balance) => Ok(balance),
+            _ => Err(()),
+        },
+        Err(_) => Err(()),
+    }
+}
+
+pub fn snapshot(storage_id: &ActorId, transaction_hash: H256, token_id: TokenId, snapshot_id: u64) {
+    msg::send(
+        *storage_id,
+        MTStorageAction::Snapshot {
+            transaction_hash,
+            token_id,
+            snapshot_id,
+        },
+        0,
+    )
+    .expect("Error in sending a message `MTStorageAction::Snapshot`.");
+}
+
+pub async fn increase_vested_balance(
+    storage_id: &ActorId,
+    transaction_hash: H256,
+    token_id: TokenId,
+    snapshot_id: u64,
+    account: &ActorId,
+    schedule: VestingSchedule,
+) -> Result<(), ()> {
+    let result = msg::send_for_reply_as::<_, MTStorageEvent>(
+        *storage_id,
+        MTStorageAction::IncreaseVestedBalance {
+            transaction_hash,
+            token_id,
+            snapshot_id,
+            account: *account,
+            schedule,
+        },
+        0,
+    )
+    .expect("Error in sending a message `MTStorageAction::IncreaseVestedBalance`.")
+    .await;
+
+    match result {
+        Ok(storage_event) => match storage_event {
+            MTStorageEvent::Ok => Ok(()),
+            _ => Err(()),
+        },
+        Err(_) => Err(()),
+    }
+}
@@ -0,0 +1,200 @@
+#![no_std]
+
+use gmeta::{metawasm, Metadata};
+use gstd::{prelude::*, ActorId};
+use mt_logic_io::*;
+use primitive_types::H256;
+
+#[metawasm]
+pub mod metafns {
+    pub type State = <MTLogicMetadata as Metadata>::State;
+
+    /// Returns the status of the transaction with `transaction_hash`.
+    pub fn transaction_status(state: State, transaction_hash: H256) -> Option<TransactionStatus> {
+        state
+            .transaction_status
+            .into_iter()
+            .find_map(|(hash, status)| (hash == transaction_hash).then_some(status))
+    }
+
+    /// Returns information about `token_id`, NFT items are resolved to their base type.
+    pub fn token_info(state: State, token_id: TokenId) -> Option<TokenInfo> {
+        let token_id = token_id & NFT_TYPE_MASK;
+
+        let creator = state
+            .token_creators
+            .into_iter()
+            .find_map(|(id, creator)| (id == token_id).then_some(creator))?;
+        let uri = state
+            .token_uris
+            .into_iter()
+            .find_map(|(id, uri)| (id == token_id).then_some(uri))
+            .unwrap_or_default();
+        let metadata = state
+            .token_metadata
+            .into_iter()
+            .find_map(|(id, metadata)| (id == token_id).then_some(metadata))
+            .unwrap_or_default();
+        let is_metadata_frozen = state.frozen_metadata.contains(&token_id);
+        let is_frozen = state.frozen_tokens.contains(&token_id);
+        let is_soulbound = state.soulbound_tokens.contains(&token_id);
+        let is_edition = state.edition_tokens.contains(&token_id);
+        let royalty = state
+            .token_royalties
+            .iter()
+            .find_map(|(id, royalty)| (*id == token_id).then_some(*royalty));
+        let transfer_fee = state
+            .transfer_fees
+            .iter()
+            .find_map(|(id, transfer_fee)| (*id == token_id).then_some(*transfer_fee));
+        let total_supply = state
+            .token_total_supply
+            .into_iter()
+            .find_map(|(id, total_supply)| (id == token_id).then_some(total_supply))
+            .unwrap_or_default();
+
+        Some(TokenInfo {
+            creator,
+            uri,
+            metadata,
+            is_metadata_frozen,
+            is_frozen,
+            is_soulbound,
+            is_edition,
+            royalty,
+            transfer_fee,
+            total_supply,
+            is_nft: token_id & NFT_BIT == NFT_BIT,
+        })
+    }
+
+    /// Returns all tokens created by `creator`.
+    pub fn tokens_by_creator(state: State, creator: ActorId) -> Vec<TokenId> {
+        let mut tokens: Vec<TokenId> = state
+            .token_creators
+            .into_iter()
+            .filter_map(|(id, token_creator)| (token_creator == creator).then_some(id))
+            .collect();
+        tokens.sort_unstable();
+        tokens
+    }
+
+    /// Returns a page of token URIs ordered by token id.
+    pub fn token_uris(state: State, after: Option<TokenId>, limit: u32) -> Vec<(TokenId, String)> {
+        paginate_sorted(state.token_uris, after, limit)
+    }
+
+    /// Returns a page of tokens total supply ordered by token id.
+    pub fn token_total_supply(
+        state: State,
+        after: Option<TokenId>,
+        limit: u32,
+    ) -> Vec<(TokenId, u128)> {
+        paginate_sorted(state.token_total_supply, after, limit)
+    }
+
+    /// Returns a page of token creators ordered by token id.
+    pub fn token_creators(
+        state: State,
+        after: Option<TokenId>,
+        limit: u32,
+    ) -> Vec<(TokenId, ActorId)> {
+        paginate_sorted(state.token_creators, after, limit)
+    }
+
+    /// Returns a page of instructions ordered by transaction hash.
+    pub fn instructions(
+        state: State,
+        after: Option<H256>,
+        limit: u32,
+    ) -> Vec<(H256, Vec<Instruction>)> {
+        paginate_sorted(state.instructions, after, limit)
+    }
+
+    /// Returns the owner of NFT item `token_id`.
+    pub fn nft_owner(state: State, token_id: TokenId) -> Option<ActorId> {
+        state
+            .nft_owners
+            .into_iter()
+            .find_map(|(id, owner)| (id == token_id).then_some(owner))
+    }
+
+    /// Returns metadata of NFT item `token_id`.
+    pub fn nft_item_metadata(state: State, token_id: TokenId) -> Option<NFTItemMetadata> {
+        state
+            .nft_metadata
+            .into_iter()
+            .find_map(|(id, metadata)| (id == token_id).then_some(metadata))
+    }
+
+    /// Returns all NFT items owned by `account`.
+    pub fn nfts_of(state: State, account: ActorId) -> Vec<TokenId> {
+        let mut nfts = state
+            .account_nfts
+            .into_iter()
+            .find_map(|(owner, nfts)| (owner == account).then_some(nfts))
+            .unwrap_or_default();
+        nfts.sort_unstable();
+        nfts
+    }
+
+    /// Returns the latest snapshot id of `token_id`, zero if no snapshots are taken.
+    pub fn snapshot_id(state: State, token_id: TokenId) -> u64 {
+        state
+            .token_snapshots
+            .into_iter()
+            .find_map(|(id, snapshot_id)| (id == token_id).then_some(snapshot_id))
+            .unwrap_or_default()
+    }
+
+    /// Returns whether `account` can't send and receive `token_id` tokens,
+    /// because the token or the account is frozen.
+    pub fn is_frozen(state: State, token_id: TokenId, account: ActorId) -> bool {
+        let token_id = token_id & NFT_TYPE_MASK;
+
+        state.frozen_tokens.contains(&token_id)
+            || state
+                .frozen_accounts
+                .into_iter()
+                .find_map(|(id, accounts)| (id == token_id).then_some(accounts))
+                .map_or(false, |accounts| accounts.contains(&account))
+    }
+
+    /// Returns whether `account` can send and receive `token_id` tokens
+    /// according to the token allowlist.
+    pub fn is_allowed(state: State, token_id: TokenId, account: ActorId) -> bool {
+        let token_id = token_id & NFT_TYPE_MASK;
+
+        !state.allowlist_tokens.contains(&token_id)
+            || state
+                .token_allowlists
+                .into_iter()
+                .find_map(|(id, accounts)| (id == token_id).then_some(accounts))
+                .map_or(false, |accounts| accounts.contains(&account))
+    }
+
+    /// Returns the pending swap by its id.
+    pub fn swap(state: State, swap_id: u64) -> Option<Swap> {
+        state
+            .swaps
+            .into_iter()
+            .find_map(|(id, swap)| (id == swap_id).then_some(swap))
+    }
+
+    /// Returns the pending scheduled transfer by its id.
+    pub fn scheduled_transfer(state: State, transfer_id: u64) -> Option<ScheduledTransfer> {
+        state
+            .scheduled_transfers
+            .into_iter()
+            .find_map(|(id, transfer)| (id == transfer_id).then_some(transfer))
+    }
+
+    /// Returns the nonce which `owner` signs with the next permit.
+    pub fn permit_nonce(state: State, owner: ActorId) -> u64 {
+        state
+            .permit_nonces
+            .into_iter()
+            .find_map(|(account, nonce)| (account == owner).then_some(nonce))
+            .unwrap_or_default()
+    }
+}
@@ -3,9 +3,13 @@
 use gmeta::{In, InOut, Metadata};
 use gstd::{prelude::*, ActorId};
 use primitive_types::H256;
+use schnorrkel::{PublicKey, Signature};
 
 pub type TokenId = u128;
 
+/// Reserved fungible token ID representing the native chain value.
+pub const NATIVE_TOKEN_ID: TokenId = 0;
+
 pub struct MTMainMetadata;
 
 impl Metadata for MTMainMetadata {
@@ -26,6 +30,10 @@ pub struct MTokenState {
     pub mt_logic_id: ActorId,
     /// Stores abstract transactions statuses.
     pub transactions: Vec<(H256, TransactionStatus)>,
+    /// Value of deposits which are in progress: `H256` -> `u128`.
+    pub deposits: Vec<(H256, u128)>,
+    /// Mapping with next transaction ids of relayed messages: `ActorId` -> `u64`.
+    pub relay_nonces: Vec<(ActorId, u64)>,
 }
 
 /// Internal transaction entities possible status.
@@ -49,6 +57,54 @@ pub enum MTokenAction {
         /// Encoded high-level [`LogicAction`] operation.
         payload: LogicAction,
     },
+    /// Handles high-level token operation signed by `signer` and sent by anyone,
+    /// so that the sender pays for gas instead of `signer`.
+    ///
+    /// Relay nonces of `signer` are tracked apart from transaction ids of its direct messages.
+    /// A retry of the transaction must be sent with the same signature.
+    ///
+    /// # Requirements
+    /// - `signature` must be a valid sr25519 signature of `signer` over [`relay_message_data`].
+    /// - `transaction_id` must be equal to the relay nonce of `signer` for a new transaction.
+    /// - `deadline` mustn't be less than the current block height.
+    ///
+    /// On success, replies with the same event as [`MTokenAction::Message`].
+    RelayMessage {
+        /// Account which signed the operation and executes it.
+        signer: ActorId,
+        /// Operation transaction id, which is also the relay nonce of `signer`.
+        transaction_id: u64,
+        /// High-level [`LogicAction`] operation.
+        payload: LogicAction,
+        /// Signature of [`relay_message_data`].
+        signature: [u8; 64],
+        /// Block height after which the signature expires.
+        deadline: u32,
+    },
+    /// Mints [`NATIVE_TOKEN_ID`] tokens equal to the attached value to [`msg::source()`](gstd::msg::source).
+    ///
+    /// Value attached to a retry of the transaction is returned with the reply,
+    /// and the deposited value is returned, if the deposit fails. If the result
+    /// of the deposit is unknown, the value is kept until a retry of the transaction finishes it.
+    ///
+    /// On success, replies with [`MTokenEvent::Ok`].
+    Deposit {
+        /// Operation transaction id(each new abstract-transaction must increase).
+        transaction_id: u64,
+    },
+    /// Burns `amount` of [`NATIVE_TOKEN_ID`] tokens of [`msg::source()`](gstd::msg::source)
+    /// and sends the equivalent value with the reply.
+    ///
+    /// Value attached to the message is returned with the reply.
+    /// If the burned value can't be sent, the transaction stays in progress to be retried.
+    ///
+    /// On success, replies with [`MTokenEvent::Ok`].
+    Withdraw {
+        /// Operation transaction id(each new abstract-transaction must increase).
+        transaction_id: u64,
+        /// Value to withdraw.
+        amount: u128,
+    },
     /// Updates unique hash-identifier or multitoken storage and logic contract code.
     ///
     /// On success, replies with [`MTokenEvent::Ok`].
@@ -60,6 +116,8 @@ pub enum MTokenAction {
     },
     /// Returns `account` token balance.
     ///
+    /// Unknown tokens and accounts have zero balance.
+    ///
     /// On success, replies with [`MTokenEvent::Balance`].
     GetBalance {
         /// Token ID to get the balance.
@@ -76,6 +134,62 @@ pub enum MTokenAction {
         /// An account that is being verified.
         approval_target: ActorId,
     },
+    /// Returns metadata of `token_id` token, NFT items share the metadata of their base type.
+    ///
+    /// On success, replies with [`MTokenEvent::TokenMetadata`].
+    /// If the token doesn't exist, replies with [`MTokenEvent::Err`].
+    GetTokenMetadata {
+        /// Token ID to get the metadata.
+        token_id: TokenId,
+    },
+    /// Returns the royalty receiver and amount for `token_id` sold for `sale_price`.
+    /// NFT items without own royalty use the royalty of their collection.
+    ///
+    /// On success, replies with [`MTokenEvent::RoyaltyInfo`].
+    /// If the token doesn't exist, replies with [`MTokenEvent::Err`].
+    RoyaltyInfo {
+        /// Token ID to get the royalty.
+        token_id: TokenId,
+        /// Sale price to calculate the royalty amount from.
+        sale_price: u128,
+    },
+    /// Returns a page of tokens held by `account`: fungible tokens with non-zero balance
+    /// ordered by token id, followed by owned NFT items ordered by token id.
+    ///
+    /// On success, replies with [`MTokenEvent::TokensOf`].
+    TokensOf {
+        /// Specifies the account whose tokens you want to find out.
+        account: ActorId,
+        /// Number of entries to skip.
+        offset: u32,
+        /// Maximum number of entries to return.
+        limit: u32,
+    },
+    /// Returns a page of `token_id` holders with their balances and the total
+    /// number of holders.
+    ///
+    /// On success, replies with [`MTokenEvent::HoldersOf`].
+    HoldersOf {
+        /// Token ID to get the holders.
+        token_id: TokenId,
+        /// Number of entries to skip.
+        offset: u32,
+        /// Maximum number of entries to return.
+        limit: u32,
+    },
+    /// Returns `account` balance of `token_id` tokens at the moment
+    /// when `snapshot_id` was taken.
+    ///
+    /// On success, replies with [`MTokenEvent::Balance`].
+    /// If `snapshot_id` isn't taken for `token_id`, replies with [`MTokenEvent::Err`].
+    BalanceOfAt {
+        /// Token ID to get the balance.
+        token_id: TokenId,
+        /// Specifies the account whose balance you want to find out.
+        account: ActorId,
+        /// Snapshot ID to get the balance at.
+        snapshot_id: u64,
+    },
     /// Deletes the stored transaction entity with its status by unique hash.
     Clear(H256),
     /// Unimplemented.
@@ -83,16 +197,170 @@ pub enum MTokenAction {
 }
 
 /// A result of processed [`MTokenAction`].
-#[derive(Encode, Decode, TypeInfo, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+#[derive(Encode, Decode, TypeInfo, PartialEq, Eq, Clone, Debug)]
 pub enum MTokenEvent {
     /// Should be returned from [`MTokenAction::Message`], if the operation is completed without errors.
     Ok,
-    /// Should be returned from [`MTokenAction::Message`], if the operation is completed with errors.
+    /// Should be returned from [`MTokenAction::Message`], if the operation is completed with errors,
+    /// or from a query, if it can't be answered.
     Err,
     /// Should be returned from [`MTokenAction::GetBalance`].
     Balance(u128),
     /// Should be returned from [`MTokenAction::GetApproval`].
     Approval(bool),
+    /// Should be returned from [`MTokenAction::GetTokenMetadata`].
+    TokenMetadata {
+        /// Token name, symbol and decimals.
+        metadata: TokenMetadata,
+        /// Base URI with token metadata.
+        uri: String,
+        /// Indicates if the metadata can't be changed anymore.
+        is_frozen: bool,
+    },
+    /// Should be returned from [`MTokenAction::RoyaltyInfo`].
+    ///
+    /// If the token has no royalty, `receiver` is [`ActorId::zero()`] and `amount` is zero.
+    RoyaltyInfo {
+        /// Account which receives the royalty.
+        receiver: ActorId,
+        /// Royalty amount.
+        amount: u128,
+    },
+    /// Should be returned from [`MTokenAction::TokensOf`].
+    TokensOf(Vec<TokenId>),
+    /// Should be returned from [`MTokenAction::HoldersOf`].
+    HoldersOf {
+        /// Requested page of holders with their balances.
+        holders: Vec<(ActorId, u128)>,
+        /// Total number of holders.
+        total: u32,
+    },
+    /// Should be returned from [`MTokenAction::Message`] with [`LogicAction::MintBatchNFT`],
+    /// if the operation is completed without errors.
+    NFTMinted(Vec<TokenId>),
+    /// Should be returned from [`MTokenAction::Message`] with [`LogicAction::UpdateTokenUri`],
+    /// if the operation is completed without errors.
+    TokenUriChanged {
+        /// Token ID which URI has been changed.
+        token_id: TokenId,
+        /// New base URI with token metadata.
+        uri: String,
+    },
+    /// Should be returned from [`MTokenAction::Message`] with [`LogicAction::Snapshot`],
+    /// if the operation is completed without errors.
+    SnapshotTaken {
+        /// Token ID which balances have been recorded.
+        token_id: TokenId,
+        /// Assigned snapshot ID.
+        snapshot_id: u64,
+    },
+    /// Should be returned from [`MTokenAction::Message`] with [`LogicAction::ProposeSwap`],
+    /// if the operation is completed without errors.
+    SwapProposed(u64),
+    /// Should be returned from [`MTokenAction::Message`] with [`LogicAction::ScheduleTransfer`],
+    /// if the operation is completed without errors.
+    TransferScheduled(u64),
+    /// Should be returned from [`MTokenAction::Message`] with [`LogicAction::Fractionalize`],
+    /// if the operation is completed without errors.
+    Fractionalized(TokenId),
+    /// Returned in [`MTokenEvent::Multicall`] for [`LogicAction::Create`] actions.
+    ///
+    /// Contains the identifier of the created token.
+    Created(TokenId),
+    /// Should be returned from [`MTokenAction::Message`] with [`LogicAction::Multicall`],
+    /// if the operation is completed without errors.
+    ///
+    /// Contains replies to the actions in their order.
+    Multicall(Vec<MTokenEvent>),
+}
+
+/// A single movement of tokens in a swap.
+#[derive(Encode, Decode, TypeInfo, PartialEq, Eq, Clone, Debug)]
+pub struct SwapLeg {
+    /// Account which gives the tokens.
+    pub from: ActorId,
+    /// Account which receives the tokens.
+    pub to: ActorId,
+    /// Identifier of the token.
+    pub token_id: TokenId,
+    /// Amount of tokens, must be 1 for NFT items which aren't editions.
+    pub amount: u128,
+}
+
+/// Metadata of a single NFT item.
+#[derive(Encode, Decode, TypeInfo, PartialEq, Eq, Clone, Debug)]
+pub enum NFTItemMetadata {
+    /// URI with item metadata.
+    Uri(String),
+    /// Raw item attributes.
+    Attributes(Vec<u8>),
+}
+
+/// Royalty paid to `receiver` from every sale of a token.
+#[derive(Encode, Decode, TypeInfo, PartialEq, Eq, Clone, Copy, Debug)]
+pub struct Royalty {
+    /// Account which receives the royalty.
+    pub receiver: ActorId,
+    /// Royalty share of the sale price in basis points, mustn't exceed [`BASIS_POINTS_DENOMINATOR`].
+    pub basis_points: u16,
+}
+
+/// Number of basis points in the whole amount.
+pub const BASIS_POINTS_DENOMINATOR: u16 = 10_000;
+
+/// Returns `basis_points` share of `amount`.
+fn basis_points_of(amount: u128, basis_points: u16) -> u128 {
+    let denominator = BASIS_POINTS_DENOMINATOR as u128;
+    let basis_points = basis_points as u128;
+
+    // Split the amount to avoid overflow on large values
+    amount / denominator * basis_points + amount % denominator * basis_points / denominator
+}
+
+impl Royalty {
+    /// Returns the royalty amount from `sale_price`.
+    pub fn amount(&self, sale_price: u128) -> u128 {
+        basis_points_of(sale_price, self.basis_points)
+    }
+}
+
+/// Fee charged from fungible token transfers.
+#[derive(Encode, Decode, TypeInfo, PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Fee {
+    /// Fixed amount of tokens per transfer.
+    Flat(u128),
+    /// Share of the transferred amount in basis points, mustn't exceed [`BASIS_POINTS_DENOMINATOR`].
+    BasisPoints(u16),
+}
+
+/// Transfer fee with the account which collects it.
+#[derive(Encode, Decode, TypeInfo, PartialEq, Eq, Clone, Copy, Debug)]
+pub struct TransferFee {
+    /// Fee charged from each transfer.
+    pub fee: Fee,
+    /// Account which receives the collected fees.
+    pub treasury: ActorId,
+}
+
+impl TransferFee {
+    /// Returns the fee charged from transfer of `amount`.
+    pub fn amount(&self, amount: u128) -> u128 {
+        match self.fee {
+            Fee::Flat(fee) => fee,
+            Fee::BasisPoints(basis_points) => basis_points_of(amount, basis_points),
+        }
+    }
+}
+
+/// Token metadata which is set on creation.
+#[derive(Encode, Decode, TypeInfo, PartialEq, Eq, Clone, Debug, Default)]
+pub struct TokenMetadata {
+    /// Token name.
+    pub name: String,
+    /// Token symbol.
+    pub symbol: String,
+    /// Number of decimals used to display token amounts, ignored for NFT.
+    pub decimals: u8,
 }
 
 /// High-level token-related operations.
@@ -103,6 +371,7 @@ pub enum LogicAction {
     /// # Requirements
     /// - `sender` must be equal to `msg_source` or `msg_source` must be approved by `sender`.
     /// - `sender` must have enough `amount` of `token_id` tokens.
+    /// - `amount` must cover the transfer fee of fungible `token_id`, if it's set.
     ///
     /// On success, replies with [`MTLogicEvent::Ok`].
     Transfer {
@@ -124,21 +393,54 @@ pub enum LogicAction {
         /// Approve flag.
         is_approved: bool,
     },
+    /// Gives `approve` of `owner` to `account` with the signature of `owner`,
+    /// so that the approval can be submitted by anyone.
+    ///
+    /// # Requirements
+    /// - `signature` must be a valid sr25519 signature of `owner` over [`permit_data`]
+    /// with the current permit nonce of `owner`.
+    /// - `deadline` mustn't be less than the current block height.
+    ///
+    /// On success, replies with [`MTLogicEvent::Ok`].
+    Permit {
+        /// Account which gives the approval.
+        owner: ActorId,
+        /// Account to which access is granted.
+        account: ActorId,
+        /// Approve flag.
+        is_approved: bool,
+        /// Block height after which the signature expires.
+        deadline: u32,
+        /// Signature of [`permit_data`].
+        signature: [u8; 64],
+    },
     /// Creates new token.
     ///
+    /// # Requirements
+    /// - `royalty` basis points mustn't exceed [`BASIS_POINTS_DENOMINATOR`].
+    ///
     /// On success, replies with [`MTLogicEvent::Ok`].
     Create {
         /// Initial token amount which will be minted to [`msg::source()`](gstd::msg::source), if `is_nft` flag is set, then ignored.
         initial_amount: u128,
         /// Base URI with token metadata.
         uri: String,
+        /// Token name, symbol and decimals.
+        metadata: TokenMetadata,
         /// Indicates if this token is nft.
         is_nft: bool,
+        /// Indicates if this token can only be minted and burned, but not transferred.
+        is_soulbound: bool,
+        /// Indicates if NFT items of this token have quantities held by many accounts,
+        /// if `is_nft` flag isn't set, then ignored.
+        is_edition: bool,
+        /// Royalty from sales of this token or its NFT items.
+        royalty: Option<Royalty>,
     },
     /// Mints new fungible `token_id` tokens for `to` with `amounts`.
     ///
     /// # Requirements
-    /// - `token_id` must be fungible.
+    /// - `token_id` must be fungible and not [`NATIVE_TOKEN_ID`].
     /// - `amounts` must be equal to `to`.
     ///
     /// On success, replies with [`MTLogicEvent::Ok`].
@@ -150,22 +452,69 @@ pub enum LogicAction {
         /// Vector with amounts.
         amounts: Vec<u128>,
     },
-    /// Mints new non-fungible `token_id` tokens for `to`.
+    /// Mints new fungible `token_id` tokens for `to`, which are locked and
+    /// released linearly over `duration` blocks after the `cliff`.
+    ///
+    /// Locked tokens can't be transferred or burned, released ones become spendable
+    /// without any additional action.
     ///
     /// # Requirements
-    /// - `token_id` must be non-fungible.
+    /// - [`msg::source()`](gstd::msg::source) must be the token creator.
+    /// - `token_id` must be fungible and not [`NATIVE_TOKEN_ID`].
+    /// - `amount` and `duration` mustn't be zero, `cliff` mustn't be greater than `duration`.
     ///
     /// On success, replies with [`MTLogicEvent::Ok`].
+    MintVested {
+        /// Identifier of the token with which mint will be performed.
+        token_id: TokenId,
+        /// Beneficiary of the vested tokens.
+        to: ActorId,
+        /// Number of vested tokens.
+        amount: u128,
+        /// Number of blocks before the first release.
+        cliff: u32,
+        /// Number of blocks after which all tokens are released.
+        duration: u32,
+    },
+    /// Mints copies of the edition NFT item for `to` with `amounts`.
+    ///
+    /// If `token_id` is an NFT type, a new item is minted, otherwise copies of
+    /// the existing item are added. Edition items are transferred and burned
+    /// like fungible tokens, copies of all items are counted in the supply of their collection.
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be the token creator.
+    /// - `token_id` must be an edition NFT type or its item.
+    /// - `amounts` must be equal to `to`.
+    ///
+    /// On success, replies with [`MTLogicEvent::NFTMinted`] with the item id.
+    MintEdition {
+        /// Identifier of the NFT type or item with which mint will be performed.
+        token_id: TokenId,
+        /// Vector with recipients.
+        to: Vec<ActorId>,
+        /// Vector with amounts.
+        amounts: Vec<u128>,
+    },
+    /// Mints new non-fungible `token_id` tokens for `to`.
+    ///
+    /// # Requirements
+    /// - `token_id` must be non-fungible and not an edition.
+    /// - `metadata` must be empty or equal to `to`.
+    ///
+    /// On success, replies with [`MTLogicEvent::NFTMinted`].
     MintBatchNFT {
         /// Identifier of the token with which mint will be performed.
         token_id: TokenId,
         /// Vector with recipients.
         to: Vec<ActorId>,
+        /// Vector with optional metadata of each minted item.
+        metadata: Vec<Option<NFTItemMetadata>>,
     },
     /// Burns new fungible `token_id` tokens from `burn_from` for `amounts`.
     ///
     /// # Requirements
-    /// - `token_id` must be fungible.
+    /// - `token_id` must be fungible and not [`NATIVE_TOKEN_ID`] or an edition NFT item.
     /// - `amounts` must be equal to `burn_from`.
     /// - `burn_from` must approve [`msg::source()`](gstd::msg::source) if not equal.
     ///
@@ -181,7 +530,7 @@ pub enum LogicAction {
     /// Burns new non-fungible `token_id` token from `from`.
     ///
     /// # Requirements
-    /// - `token_id` must be non-fungible.
+    /// - `token_id` must be non-fungible and not an edition.
     /// - `from` must approve [`msg::source()`](gstd::msg::source) if not equal.
     /// - `from` must be owner of `token_id`.
     ///
@@ -192,6 +541,273 @@ pub enum LogicAction {
         /// Burn target(account).
         from: ActorId,
     },
+    /// Burns non-fungible `ids` tokens from `from` accounts.
+    ///
+    /// Either all tokens are burned or none of them.
+    ///
+    /// # Requirements
+    /// - `ids` and `from` must have the same length.
+    /// - `ids` must be unique, non-fungible and not editions.
+    /// - Every `from` account must approve [`msg::source()`](gstd::msg::source) if not equal.
+    /// - Every `from` account must be owner of the token with the same index in `ids`.
+    ///
+    /// On success, replies with [`MTLogicEvent::Ok`].
+    BurnBatchNFT {
+        /// Identifiers of the tokens to burn.
+        ids: Vec<TokenId>,
+        /// Owners of the tokens.
+        from: Vec<ActorId>,
+    },
+    /// Burns all non-fungible tokens of `from`, except edition NFT items.
+    ///
+    /// Either all tokens are burned or none of them.
+    ///
+    /// # Requirements
+    /// - `from` must approve [`msg::source()`](gstd::msg::source) if not equal.
+    ///
+    /// On success, replies with [`MTLogicEvent::Ok`].
+    BurnAllNFT {
+        /// Owner of the tokens.
+        from: ActorId,
+    },
+    /// Locks NFT item `token_id` of [`msg::source()`](gstd::msg::source) and mints
+    /// `supply` of a new fungible token representing its fractions to it.
+    ///
+    /// The fractional token takes metadata and URI of the NFT collection, it can't be minted anymore.
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be owner of `token_id`.
+    /// - `token_id` mustn't be soulbound or frozen.
+    /// - `supply` mustn't be zero.
+    ///
+    /// On success, replies with [`MTLogicEvent::Fractionalized`].
+    Fractionalize {
+        /// Identifier of the NFT item to lock.
+        token_id: TokenId,
+        /// Number of minted fractions.
+        supply: u128,
+    },
+    /// Burns the whole supply of fractional `token_id` tokens held by
+    /// [`msg::source()`](gstd::msg::source) and unlocks the NFT item to it.
+    ///
+    /// # Requirements
+    /// - `token_id` must be a fractional token, which NFT item isn't redeemed yet.
+    /// - [`msg::source()`](gstd::msg::source) must hold the whole supply of `token_id`.
+    ///
+    /// On success, replies with [`MTLogicEvent::Ok`].
+    Redeem {
+        /// Identifier of the fractional token.
+        token_id: TokenId,
+    },
+    /// Updates base URI of `token_id` token.
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be creator of `token_id`.
+    /// - `token_id` metadata mustn't be frozen.
+    ///
+    /// On success, replies with [`MTLogicEvent::TokenUriChanged`].
+    UpdateTokenUri {
+        /// Identifier of the token which URI will be updated.
+        token_id: TokenId,
+        /// New base URI with token metadata.
+        uri: String,
+    },
+    /// Permanently forbids any changes of `token_id` metadata.
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be creator of `token_id`.
+    ///
+    /// On success, replies with [`MTLogicEvent::Ok`].
+    FreezeMetadata {
+        /// Identifier of the token which metadata will be frozen.
+        token_id: TokenId,
+    },
+    /// Overrides or resets the collection royalty for `token_id` NFT item.
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be creator of `token_id` collection.
+    /// - `token_id` must be a minted NFT item.
+    /// - `royalty` basis points mustn't exceed [`BASIS_POINTS_DENOMINATOR`].
+    ///
+    /// On success, replies with [`MTLogicEvent::Ok`].
+    SetItemRoyalty {
+        /// Identifier of the NFT item.
+        token_id: TokenId,
+        /// New item royalty, `None` resets it to the collection royalty.
+        royalty: Option<Royalty>,
+    },
+    /// Sets or removes the fee charged from transfers of fungible `token_id` tokens.
+    /// The fee is deducted from the transferred amount and credited to the treasury.
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be creator of `token_id`.
+    /// - `token_id` must be fungible.
+    /// - [`Fee::BasisPoints`] mustn't exceed [`BASIS_POINTS_DENOMINATOR`].
+    ///
+    /// On success, replies with [`MTLogicEvent::Ok`].
+    SetTransferFee {
+        /// Identifier of the token.
+        token_id: TokenId,
+        /// New transfer fee, `None` removes it.
+        fee: Option<TransferFee>,
+    },
+    /// Freezes or unfreezes all transfers, mints and burns of `token_id` tokens.
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be creator of `token_id`.
+    ///
+    /// On success, replies with [`MTLogicEvent::Ok`].
+    FreezeToken {
+        /// Identifier of the token, NFT items are frozen with their base type.
+        token_id: TokenId,
+        /// Freeze flag.
+        is_frozen: bool,
+    },
+    /// Forbids or allows `account` to send and receive `token_id` tokens.
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be creator of `token_id`.
+    ///
+    /// On success, replies with [`MTLogicEvent::Ok`].
+    FreezeAccount {
+        /// Identifier of the token, NFT items are frozen with their base type.
+        token_id: TokenId,
+        /// Account to freeze.
+        account: ActorId,
+        /// Freeze flag.
+        is_frozen: bool,
+    },
+    /// Enables or disables allowlist mode of `token_id` token. In this mode
+    /// transfers and mints succeed only between accounts on the token allowlist.
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be creator of `token_id`.
+    ///
+    /// On success, replies with [`MTLogicEvent::Ok`].
+    SetAllowlistMode {
+        /// Identifier of the token, NFT items share the mode of their base type.
+        token_id: TokenId,
+        /// Allowlist mode flag.
+        is_enabled: bool,
+    },
+    /// Adds `accounts` to or removes them from the allowlist of `token_id` token.
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be creator of `token_id`.
+    ///
+    /// On success, replies with [`MTLogicEvent::Ok`].
+    UpdateAllowlist {
+        /// Identifier of the token, NFT items share the allowlist of their base type.
+        token_id: TokenId,
+        /// Accounts to update.
+        accounts: Vec<ActorId>,
+        /// Whether `accounts` are added or removed.
+        is_allowed: bool,
+    },
+    /// Proposes a swap which settles all `legs` at once, when every party
+    /// (an account in any leg) has confirmed it. The proposer is confirmed immediately.
+    ///
+    /// Tokens offered by a party are escrowed by the logic when it confirms the swap
+    /// and are returned to it when the swap is cancelled, e.g. after the deadline.
+    /// Transfer fees aren't charged on swap legs.
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be a party of the swap.
+    /// - `legs` mustn't be empty, each leg must move a non-zero amount between different accounts.
+    /// - `deadline` must be greater than the current block height.
+    /// - [`msg::source()`](gstd::msg::source) must have enough offered tokens.
+    ///
+    /// On success, replies with [`MTLogicEvent::SwapProposed`].
+    ProposeSwap {
+        /// Token movements of the swap.
+        legs: Vec<SwapLeg>,
+        /// Block height after which the swap can't be confirmed anymore.
+        deadline: u32,
+    },
+    /// Confirms the swap and escrows tokens offered by [`msg::source()`](gstd::msg::source),
+    /// the last confirmation settles it.
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be a party of the swap which hasn't confirmed it yet.
+    /// - [`msg::source()`](gstd::msg::source) must have enough offered tokens.
+    /// - The swap deadline mustn't be passed.
+    ///
+    /// On success, replies with [`MTLogicEvent::Ok`].
+    ConfirmSwap {
+        /// Identifier of the swap.
+        swap_id: u64,
+    },
+    /// Cancels the swap and returns escrowed tokens to the parties which offered them.
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be a party of the swap
+    /// or the swap deadline must be passed.
+    ///
+    /// On success, replies with [`MTLogicEvent::Ok`].
+    CancelSwap {
+        /// Identifier of the swap.
+        swap_id: u64,
+    },
+    /// Escrows fungible `amount` of `token_id` tokens from [`msg::source()`](gstd::msg::source)
+    /// and transfers them to `recipient` at `at_block` with a delayed message.
+    ///
+    /// Gas for the delayed transfer is reserved from this message. If the transfer
+    /// isn't possible at `at_block` anymore or fails, the tokens are returned to the sender.
+    /// Transfer fees aren't charged on scheduled transfers.
+    ///
+    /// # Requirements
+    /// - `token_id` must be fungible or an edition NFT item.
+    /// - `amount` mustn't be zero.
+    /// - `at_block` must be greater than the current block height
+    /// and mustn't exceed it by more than 100 blocks, the lifetime of the gas reservation.
+    ///
+    /// On success, replies with [`MTLogicEvent::TransferScheduled`].
+    ScheduleTransfer {
+        /// Identifier of the token to transfer.
+        token_id: TokenId,
+        /// Account which receives the tokens.
+        recipient: ActorId,
+        /// Number of tokens to transfer.
+        amount: u128,
+        /// Block height at which the transfer is executed.
+        at_block: u32,
+    },
+    /// Cancels the scheduled transfer and returns the escrowed tokens to the sender.
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be the sender of the scheduled transfer.
+    /// - The transfer mustn't be executed yet.
+    ///
+    /// On success, replies with [`MTLogicEvent::Ok`].
+    CancelScheduledTransfer {
+        /// Identifier of the scheduled transfer.
+        transfer_id: u64,
+    },
+    /// Executes `actions` in their order as one transaction.
+    ///
+    /// If any action fails, balance changes of the executed ones are compensated.
+    /// Other changes of actions take effect only when all of them succeed.
+    ///
+    /// # Requirements
+    /// - `actions` may only contain [`LogicAction::Create`], [`LogicAction::Transfer`],
+    /// [`LogicAction::MintBatchFT`], [`LogicAction::BurnBatchFT`], [`LogicAction::MintBatchNFT`],
+    /// [`LogicAction::Approve`] and [`LogicAction::Permit`].
+    /// - [`LogicAction::Transfer`] must transfer fungible tokens or edition NFT items.
+    /// - Every action must meet its own requirements.
+    ///
+    /// On success, replies with [`MTLogicEvent::Multicall`].
+    Multicall(Vec<LogicAction>),
+    /// Takes a snapshot of fungible `token_id` balances and assigns it the next snapshot id.
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be admin or creator of `token_id`.
+    /// - `token_id` must be fungible.
+    ///
+    /// On success, replies with [`MTLogicEvent::SnapshotTaken`].
+    Snapshot {
+        /// Identifier of the token which balances will be recorded.
+        token_id: TokenId,
+    },
 }
 
 /// Initializes the contract.
@@ -206,3 +822,73 @@ pub struct InitMToken {
     /// Unique hash-identifier of logic contract code.
     pub mt_logic_code_hash: H256,
 }
+
+/// Signing context of [`MTokenAction::RelayMessage`] and [`LogicAction::Permit`] signatures.
+pub const SIGNING_CONTEXT: &[u8] = b"substrate";
+
+/// Tag of [`relay_message_data`].
+const RELAY_MESSAGE_TAG: &[u8] = b"relay_message";
+
+/// Tag of [`permit_data`].
+const PERMIT_TAG: &[u8] = b"permit";
+
+/// Returns the data which `signer` signs for [`MTokenAction::RelayMessage`].
+///
+/// The data is bound to the multitoken main contract `mtoken_id`, so the signature
+/// can't be replayed on another deployment, and is tagged, so it can't be taken for a permit.
+pub fn relay_message_data(
+    mtoken_id: &ActorId,
+    signer: &ActorId,
+    transaction_id: u64,
+    payload: &LogicAction,
+    deadline: u32,
+) -> Vec<u8> {
+    (
+        RELAY_MESSAGE_TAG,
+        mtoken_id,
+        signer,
+        transaction_id,
+        payload,
+        deadline,
+    )
+        .encode()
+}
+
+/// Returns the data which `owner` signs for [`LogicAction::Permit`].
+///
+/// The data is bound to the multitoken main contract `mtoken_id` and is tagged,
+/// so it can't be taken for a relayed message.
+pub fn permit_data(
+    mtoken_id: &ActorId,
+    owner: &ActorId,
+    account: &ActorId,
+    is_approved: bool,
+    nonce: u64,
+    deadline: u32,
+) -> Vec<u8> {
+    (
+        PERMIT_TAG,
+        mtoken_id,
+        owner,
+        account,
+        is_approved,
+        nonce,
+        deadline,
+    )
+        .encode()
+}
+
+/// Returns whether `signature` of `data` is a valid sr25519 signature of `signer`.
+pub fn verify_signature(signer: &ActorId, data: &[u8], signature: &[u8; 64]) -> bool {
+    let public_key = match PublicKey::from_bytes(signer.as_ref()) {
+        Ok(public_key) => public_key,
+        Err(_) => return false,
+    };
+
+    match Signature::from_bytes(signature) {
+        Ok(signature) => public_key
+            .verify_simple(SIGNING_CONTEXT, data, &signature)
+            .is_ok(),
+        Err(_) => false,
+    }
+}
@@ -1,6 +1,6 @@
 #![no_std]
 
-use gstd::{msg, prelude::*, prog::ProgramGenerator, ActorId};
+use gstd::{exec, msg, prelude::*, prog::ProgramGenerator, ActorId};
 use hashbrown::HashMap;
 use mt_logic_io::{InitMTLogic, MTLogicAction, MTLogicEvent, TokenId};
 use mt_main_io::*;
@@ -11,18 +11,18 @@ struct MToken {
     admin: ActorId,
     mt_logic_id: ActorId,
     transactions: HashMap<H256, TransactionStatus>,
+    deposits: HashMap<H256, u128>,
+    relay_nonces: HashMap<ActorId, u64>,
 }
 
 impl MToken {
     /// Accepts the payload message that will be sent to the logic token contract.
     ///
     /// Arguments:
-    /// * `transaction_id`: the id of the transaction indicated by the actor that has sent that message;
+    /// * `account`: the actor that has sent or signed that message;
+    /// * `transaction_hash`: the hash of the transaction of that message;
     /// * `payload`: the message payload that will be sent to the logic token contract
-    async fn message(&mut self, transaction_id: u64, payload: &[u8]) {
-        // Get the transaction hash from `msg::source` and `transaction_id`
-        // Tracking the trandaction ids is a responsibility of the account or programs that sent that transaction.
-        let transaction_hash = get_hash(&msg::source(), transaction_id);
+    async fn message(&mut self, account: &ActorId, transaction_hash: H256, payload: &[u8]) {
         let transaction = self.transactions.get(&transaction_hash);
 
         match transaction {
@@ -31,13 +31,13 @@ impl MToken {
                 // and send message to the logic contract.
                 self.transactions
                     .insert(transaction_hash, TransactionStatus::InProgress);
-                self.send_message_then_reply(transaction_hash, payload)
+                self.send_message_then_reply(account, transaction_hash, payload)
                     .await;
             }
             // The case when there was not enough gas to process the result of the message to the logic contract.
             Some(transaction_status) => match transaction_status {
                 TransactionStatus::InProgress => {
-                    self.send_message_then_reply(transaction_hash, payload)
+                    self.send_message_then_reply(account, transaction_hash, payload)
                         .await;
                 }
                 TransactionStatus::Success => {
@@ -50,13 +50,193 @@ impl MToken {
         }
     }
 
-    async fn send_message_then_reply(&mut self, transaction_hash: H256, payload: &[u8]) {
-        let result = self.send_message(transaction_hash, payload).await;
+    async fn relay_message(
+        &mut self,
+        signer: &ActorId,
+        transaction_id: u64,
+        payload: LogicAction,
+        signature: &[u8; 64],
+        deadline: u32,
+    ) {
+        let data = relay_message_data(
+            &exec::program_id(),
+            signer,
+            transaction_id,
+            &payload,
+            deadline,
+        );
+
+        if !verify_signature(signer, &data, signature) {
+            reply_err();
+            return;
+        }
+
+        // A new transaction must use the next relay nonce, retries are handled as usual.
+        // Relay nonces are hashed apart from transaction ids of messages sent by the signer.
+        let transaction_hash = get_relay_hash(signer, transaction_id);
+        if !self.transactions.contains_key(&transaction_hash) {
+            let nonce = self.relay_nonces.entry(*signer).or_default();
+
+            if exec::block_height() > deadline || transaction_id != *nonce {
+                reply_err();
+                return;
+            }
+
+            *nonce = nonce.checked_add(1).expect("Math overflow!");
+        }
+
+        self.message(signer, transaction_hash, &payload.encode())
+            .await;
+    }
+
+    async fn deposit(&mut self, transaction_id: u64) {
+        let transaction_hash = get_hash(&msg::source(), transaction_id);
+        // Value of the first message is deposited, value of the retries is returned
+        let mut refund = msg::value();
+
+        match self.transactions.get(&transaction_hash) {
+            None => {
+                self.transactions
+                    .insert(transaction_hash, TransactionStatus::InProgress);
+                self.deposits.insert(transaction_hash, refund);
+                refund = 0;
+            }
+            Some(TransactionStatus::InProgress) => {}
+            Some(TransactionStatus::Success) => {
+                msg::reply(MTokenEvent::Ok, refund).expect("Error in a reply `MTokenEvent::Ok`.");
+                return;
+            }
+            Some(TransactionStatus::Failure) => {
+                msg::reply(MTokenEvent::Err, refund).expect("Error in a reply `MTokenEvent::Err`.");
+                return;
+            }
+        }
+
+        let amount = self
+            .deposits
+            .get(&transaction_hash)
+            .copied()
+            .expect("Deposit must be stored.");
+        let result = self
+            .send_native_message(MTLogicAction::Deposit {
+                transaction_hash,
+                account: msg::source(),
+                amount,
+            })
+            .await;
+
         match result {
-            Ok(()) => {
+            TransactionStatus::Success => {
+                self.deposits.remove(&transaction_hash);
                 self.transactions
                     .insert(transaction_hash, TransactionStatus::Success);
-                reply_ok();
+                msg::reply(MTokenEvent::Ok, refund).expect("Error in a reply `MTokenEvent::Ok`.");
+            }
+            TransactionStatus::Failure => {
+                self.deposits.remove(&transaction_hash);
+                self.transactions
+                    .insert(transaction_hash, TransactionStatus::Failure);
+                msg::reply(MTokenEvent::Err, refund + amount)
+                    .expect("Error in a reply `MTokenEvent::Err`.");
+            }
+            // The deposit may be minted already, so its value is kept until a retry finishes it
+            TransactionStatus::InProgress => {
+                msg::reply(MTokenEvent::Err, refund).expect("Error in a reply `MTokenEvent::Err`.");
+            }
+        }
+    }
+
+    async fn withdraw(&mut self, transaction_id: u64, amount: u128) {
+        let transaction_hash = get_hash(&msg::source(), transaction_id);
+        // Value attached to a withdrawal isn't deposited, it's returned with the reply
+        let refund = msg::value();
+
+        let value = amount.checked_add(refund).expect("Math overflow!");
+
+        match self.transactions.get(&transaction_hash) {
+            // Tokens aren't burned, if their value can't be sent
+            None if exec::value_available() < value => {
+                self.transactions
+                    .insert(transaction_hash, TransactionStatus::Failure);
+                msg::reply(MTokenEvent::Err, refund).expect("Error in a reply `MTokenEvent::Err`.");
+                return;
+            }
+            None => {
+                self.transactions
+                    .insert(transaction_hash, TransactionStatus::InProgress);
+            }
+            Some(TransactionStatus::InProgress) => {}
+            Some(TransactionStatus::Success) => {
+                msg::reply(MTokenEvent::Ok, refund).expect("Error in a reply `MTokenEvent::Ok`.");
+                return;
+            }
+            Some(TransactionStatus::Failure) => {
+                msg::reply(MTokenEvent::Err, refund).expect("Error in a reply `MTokenEvent::Err`.");
+                return;
+            }
+        }
+
+        let result = self
+            .send_native_message(MTLogicAction::Withdraw {
+                transaction_hash,
+                account: msg::source(),
+                amount,
+            })
+            .await;
+
+        match result {
+            // The transaction stays in progress to be retried, if the value can't be sent
+            TransactionStatus::Success => match msg::reply(MTokenEvent::Ok, value) {
+                Ok(_) => {
+                    self.transactions
+                        .insert(transaction_hash, TransactionStatus::Success);
+                }
+                Err(_) => {
+                    msg::reply(MTokenEvent::Err, refund)
+                        .expect("Error in a reply `MTokenEvent::Err`.");
+                }
+            },
+            TransactionStatus::Failure => {
+                self.transactions
+                    .insert(transaction_hash, TransactionStatus::Failure);
+                msg::reply(MTokenEvent::Err, refund).expect("Error in a reply `MTokenEvent::Err`.");
+            }
+            // Tokens may be burned already, so the transaction stays in progress to be retried
+            TransactionStatus::InProgress => {
+                msg::reply(MTokenEvent::Err, refund).expect("Error in a reply `MTokenEvent::Err`.");
+            }
+        }
+    }
+
+    /// Returns the status of a native value operation in the logic contract.
+    ///
+    /// The status is unknown and stays in progress, if the logic contract
+    /// fails without replying with the result, e.g. when it runs out of gas.
+    async fn send_native_message(&self, action: MTLogicAction) -> TransactionStatus {
+        let result =
+            msg::send_for_reply_as::<MTLogicAction, MTLogicEvent>(self.mt_logic_id, action, 0)
+                .expect("Error in sending a message to the multitoken logic contract.")
+                .await;
+
+        match result {
+            Ok(MTLogicEvent::Ok) => TransactionStatus::Success,
+            Ok(MTLogicEvent::Err) => TransactionStatus::Failure,
+            _ => TransactionStatus::InProgress,
+        }
+    }
+
+    async fn send_message_then_reply(
+        &mut self,
+        account: &ActorId,
+        transaction_hash: H256,
+        payload: &[u8],
+    ) {
+        let result = self.send_message(account, transaction_hash, payload).await;
+        match result {
+            Ok(event) => {
+                self.transactions
+                    .insert(transaction_hash, TransactionStatus::Success);
+                msg::reply(event, 0).expect("Error in a reply `MTokenEvent`.");
             }
             Err(()) => {
                 self.transactions
@@ -66,12 +246,17 @@ impl MToken {
         };
     }
 
-    async fn send_message(&self, transaction_hash: H256, payload: &[u8]) -> Result<(), ()> {
+    async fn send_message(
+        &self,
+        account: &ActorId,
+        transaction_hash: H256,
+        payload: &[u8],
+    ) -> Result<MTokenEvent, ()> {
         let result = msg::send_for_reply_as::<MTLogicAction, MTLogicEvent>(
             self.mt_logic_id,
             MTLogicAction::Message {
                 transaction_hash,
-                account: msg::source(),
+                account: *account,
                 payload: payload.to_vec(),
             },
             0,
@@ -80,7 +265,36 @@ impl MToken {
         .await;
 
         match result {
-            Ok(MTLogicEvent::Ok) => Ok(()),
+            Ok(event) => Self::message_reply(event),
+            Err(_) => Err(()),
+        }
+    }
+
+    fn message_reply(event: MTLogicEvent) -> Result<MTokenEvent, ()> {
+        match event {
+            MTLogicEvent::Ok => Ok(MTokenEvent::Ok),
+            MTLogicEvent::NFTMinted(ids) => Ok(MTokenEvent::NFTMinted(ids)),
+            MTLogicEvent::TokenUriChanged { token_id, uri } => {
+                Ok(MTokenEvent::TokenUriChanged { token_id, uri })
+            }
+            MTLogicEvent::SwapProposed(swap_id) => Ok(MTokenEvent::SwapProposed(swap_id)),
+            MTLogicEvent::TransferScheduled(transfer_id) => {
+                Ok(MTokenEvent::TransferScheduled(transfer_id))
+            }
+            MTLogicEvent::Fractionalized(token_id) => Ok(MTokenEvent::Fractionalized(token_id)),
+            MTLogicEvent::Created(token_id) => Ok(MTokenEvent::Created(token_id)),
+            MTLogicEvent::SnapshotTaken {
+                token_id,
+                snapshot_id,
+            } => Ok(MTokenEvent::SnapshotTaken {
+                token_id,
+                snapshot_id,
+            }),
+            MTLogicEvent::Multicall(events) => events
+                .into_iter()
+                .map(Self::message_reply)
+                .collect::<Result<_, _>>()
+                .map(MTokenEvent::Multicall),
             _ => Err(()),
         }
     }
@@ -98,9 +312,35 @@ impl MToken {
         .await
         .expect("Unable to decode `MTLogicEvent`.");
 
-        if let MTLogicEvent::Balance(balance) = reply {
-            msg::reply(MTokenEvent::Balance(balance), 0)
-                .expect("Error in a reply `MTokenEvent::Balance`.");
+        match reply {
+            MTLogicEvent::Balance(balance) => {
+                msg::reply(MTokenEvent::Balance(balance), 0)
+                    .expect("Error in a reply `MTokenEvent::Balance`.");
+            }
+            _ => reply_err(),
+        }
+    }
+
+    async fn balance_of_at(&self, token_id: TokenId, account: &ActorId, snapshot_id: u64) {
+        let reply = msg::send_for_reply_as::<MTLogicAction, MTLogicEvent>(
+            self.mt_logic_id,
+            MTLogicAction::BalanceOfAt {
+                token_id,
+                account: *account,
+                snapshot_id,
+            },
+            0,
+        )
+        .expect("Error in sending a message `MTLogicAction::BalanceOfAt`.")
+        .await
+        .expect("Unable to decode `MTLogicEvent`.");
+
+        match reply {
+            MTLogicEvent::Balance(balance) => {
+                msg::reply(MTokenEvent::Balance(balance), 0)
+                    .expect("Error in a reply `MTokenEvent::Balance`.");
+            }
+            _ => reply_err(),
         }
     }
 
@@ -117,9 +357,110 @@ impl MToken {
         .await
         .expect("Unable to decode `MTLogicEvent`.");
 
-        if let MTLogicEvent::Approval(approval) = reply {
-            msg::reply(MTokenEvent::Approval(approval), 0)
-                .expect("Error in a reply `MTokenEvent::Approval`.");
+        match reply {
+            MTLogicEvent::Approval(approval) => {
+                msg::reply(MTokenEvent::Approval(approval), 0)
+                    .expect("Error in a reply `MTokenEvent::Approval`.");
+            }
+            _ => reply_err(),
+        }
+    }
+
+    async fn get_token_metadata(&self, token_id: TokenId) {
+        let reply = msg::send_for_reply_as::<MTLogicAction, MTLogicEvent>(
+            self.mt_logic_id,
+            MTLogicAction::GetTokenMetadata { token_id },
+            0,
+        )
+        .expect("Error in sending a message `MTLogicAction::GetTokenMetadata`.")
+        .await
+        .expect("Unable to decode `MTLogicEvent`.");
+
+        match reply {
+            MTLogicEvent::TokenMetadata {
+                metadata,
+                uri,
+                is_frozen,
+            } => {
+                msg::reply(
+                    MTokenEvent::TokenMetadata {
+                        metadata,
+                        uri,
+                        is_frozen,
+                    },
+                    0,
+                )
+                .expect("Error in a reply `MTokenEvent::TokenMetadata`.");
+            }
+            _ => reply_err(),
+        }
+    }
+
+    async fn royalty_info(&self, token_id: TokenId, sale_price: u128) {
+        let reply = msg::send_for_reply_as::<MTLogicAction, MTLogicEvent>(
+            self.mt_logic_id,
+            MTLogicAction::RoyaltyInfo {
+                token_id,
+                sale_price,
+            },
+            0,
+        )
+        .expect("Error in sending a message `MTLogicAction::RoyaltyInfo`.")
+        .await
+        .expect("Unable to decode `MTLogicEvent`.");
+
+        match reply {
+            MTLogicEvent::RoyaltyInfo { receiver, amount } => {
+                msg::reply(MTokenEvent::RoyaltyInfo { receiver, amount }, 0)
+                    .expect("Error in a reply `MTokenEvent::RoyaltyInfo`.");
+            }
+            _ => reply_err(),
+        }
+    }
+
+    async fn tokens_of(&self, account: &ActorId, offset: u32, limit: u32) {
+        let reply = msg::send_for_reply_as::<MTLogicAction, MTLogicEvent>(
+            self.mt_logic_id,
+            MTLogicAction::TokensOf {
+                account: *account,
+                offset,
+                limit,
+            },
+            0,
+        )
+        .expect("Error in sending a message `MTLogicAction::TokensOf`.")
+        .await
+        .expect("Unable to decode `MTLogicEvent`.");
+
+        match reply {
+            MTLogicEvent::TokensOf(tokens) => {
+                msg::reply(MTokenEvent::TokensOf(tokens), 0)
+                    .expect("Error in a reply `MTokenEvent::TokensOf`.");
+            }
+            _ => reply_err(),
+        }
+    }
+
+    async fn holders_of(&self, token_id: TokenId, offset: u32, limit: u32) {
+        let reply = msg::send_for_reply_as::<MTLogicAction, MTLogicEvent>(
+            self.mt_logic_id,
+            MTLogicAction::HoldersOf {
+                token_id,
+                offset,
+                limit,
+            },
+            0,
+        )
+        .expect("Error in sending a message `MTLogicAction::HoldersOf`.")
+        .await
+        .expect("Unable to decode `MTLogicEvent`.");
+
+        match reply {
+            MTLogicEvent::HoldersOf { holders, total } => {
+                msg::reply(MTokenEvent::HoldersOf { holders, total }, 0)
+                    .expect("Error in a reply `MTokenEvent::HoldersOf`.");
+            }
+            _ => reply_err(),
         }
     }
 
@@ -188,9 +529,30 @@ async fn main() {
             transaction_id,
             payload,
         } => {
+            // Get the transaction hash from `account` and `transaction_id`
+            // Tracking the trandaction ids is a responsibility of the account or programs that sent that transaction.
+            let transaction_hash = get_hash(&msg::source(), transaction_id);
             let payload_encoded = payload.encode();
-            mtoken.message(transaction_id, &payload_encoded).await
+            mtoken
+                .message(&msg::source(), transaction_hash, &payload_encoded)
+                .await
         }
+        MTokenAction::RelayMessage {
+            signer,
+            transaction_id,
+            payload,
+            signature,
+            deadline,
+        } => {
+            mtoken
+                .relay_message(&signer, transaction_id, payload, &signature, deadline)
+                .await
+        }
+        MTokenAction::Deposit { transaction_id } => mtoken.deposit(transaction_id).await,
+        MTokenAction::Withdraw {
+            transaction_id,
+            amount,
+        } => mtoken.withdraw(transaction_id, amount).await,
         MTokenAction::UpdateLogicContract {
             mt_logic_code_hash,
             storage_code_hash,
@@ -203,6 +565,26 @@ async fn main() {
             account,
             approval_target,
         } => mtoken.get_approval(&account, &approval_target).await,
+        MTokenAction::BalanceOfAt {
+            token_id,
+            account,
+            snapshot_id,
+        } => mtoken.balance_of_at(token_id, &account, snapshot_id).await,
+        MTokenAction::GetTokenMetadata { token_id } => mtoken.get_token_metadata(token_id).await,
+        MTokenAction::RoyaltyInfo {
+            token_id,
+            sale_price,
+        } => mtoken.royalty_info(token_id, sale_price).await,
+        MTokenAction::TokensOf {
+            account,
+            offset,
+            limit,
+        } => mtoken.tokens_of(&account, offset, limit).await,
+        MTokenAction::HoldersOf {
+            token_id,
+            offset,
+            limit,
+        } => mtoken.holders_of(token_id, offset, limit).await,
         MTokenAction::MigrateStorageAddresses => {
             unimplemented!()
         }
@@ -216,6 +598,8 @@ extern "C" fn state() {
         admin: token.admin,
         mt_logic_id: token.mt_logic_id,
         transactions: token.transactions.iter().map(|(a, b)| (*a, *b)).collect(),
+        deposits: token.deposits.iter().map(|(a, b)| (*a, *b)).collect(),
+        relay_nonces: token.relay_nonces.iter().map(|(a, b)| (*a, *b)).collect(),
     };
 
     msg::reply(token_state, 0).expect("Failed to share state.");
@@ -240,3 +624,12 @@ pub fn get_hash(account: &ActorId, transaction_id: u64) -> H256 {
     let transaction_id = transaction_id.to_be_bytes();
     sp_core_hashing::blake2_256(&[account.as_slice(), transaction_id.as_slice()].concat()).into()
 }
+
+pub fn get_relay_hash(signer: &ActorId, nonce: u64) -> H256 {
+    let signer: [u8; 32] = (*signer).into();
+    let nonce = nonce.to_be_bytes();
+    sp_core_hashing::blake2_256(
+        &[b"relay".as_slice(), signer.as_slice(), nonce.as_slice()].concat(),
+    )
+    .into()
+}
@@ -0,0 +1,5 @@
+use gear_wasm_builder::WasmBuilder;
+
+fn main() {
+    WasmBuilder::new_metawasm().build();
+}
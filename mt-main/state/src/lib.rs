@@ -0,0 +1,38 @@
+#![no_std]
+
+use gmeta::{metawasm, Metadata};
+use gstd::ActorId;
+use mt_main_io::*;
+use primitive_types::H256;
+
+#[metawasm]
+pub mod metafns {
+    pub type State = <MTMainMetadata as Metadata>::State;
+
+    /// Returns the multitoken main contract admin.
+    pub fn admin(state: State) -> ActorId {
+        state.admin
+    }
+
+    /// Returns the address of multitoken logic contract.
+    pub fn mt_logic_id(state: State) -> ActorId {
+        state.mt_logic_id
+    }
+
+    /// Returns the status of the abstract transaction with `transaction_hash`.
+    pub fn transaction_status(state: State, transaction_hash: H256) -> Option<TransactionStatus> {
+        state
+            .transactions
+            .into_iter()
+            .find_map(|(hash, status)| (hash == transaction_hash).then_some(status))
+    }
+
+    /// Returns the transaction id of the next message relayed for `signer`.
+    pub fn relay_nonce(state: State, signer: ActorId) -> u64 {
+        state
+            .relay_nonces
+            .into_iter()
+            .find_map(|(account, nonce)| (account == signer).then_some(nonce))
+            .unwrap_or_default()
+    }
+}
@@ -1,10 +1,17 @@
 mod utils;
 
-use gstd::prelude::*;
+use gstd::{prelude::*, ActorId};
 use gtest::{Program, System};
-use mt_logic_io::TokenId;
+use mt_logic_io::{MTLogicAction, MTLogicEvent, TokenId, NFT_BIT};
+use mt_main_io::{
+    relay_message_data, Fee, LogicAction, MTokenAction, MTokenEvent, MTokenState, SwapLeg,
+    TokenMetadata, TransferFee, NATIVE_TOKEN_ID, SIGNING_CONTEXT,
+};
+use mt_storage_io::{InitMTStorage, MTStorageAction, MTStorageEvent};
+use primitive_types::H256;
+use schnorrkel::{ExpansionMode, Keypair, MiniSecretKey};
 use std::mem;
-use utils::{MToken, USER_ACCOUNTS};
+use utils::{MToken, ROOT_ACCOUNT, USER_ACCOUNTS};
 
 #[test]
 fn success_create_ft() {
@@ -299,3 +306,1664 @@ fn success_transfer_ft() {
     );
     assert_eq!(mtoken.get_balance(token_id, USER_ACCOUNTS[1]), 0);
 }
+
+#[test]
+fn success_get_balance_and_approval_of_unknown_entries() {
+    let system = System::new();
+    system.init_logger();
+
+    let tx_id = 0;
+    let initial_amount = 1000000;
+    let token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2);
+    let unknown_token_id: TokenId = 2 << (mem::size_of::<TokenId>() * 8 / 2);
+    let mtoken = Program::mtoken(&system);
+
+    mtoken.create(
+        tx_id,
+        USER_ACCOUNTS[0],
+        initial_amount,
+        String::from("https://example.com"),
+        false,
+        false,
+    );
+    assert_eq!(
+        mtoken.get_balance(token_id, USER_ACCOUNTS[0]),
+        initial_amount
+    );
+    assert_eq!(mtoken.get_balance(unknown_token_id, USER_ACCOUNTS[0]), 0);
+    assert!(!mtoken.get_approval(USER_ACCOUNTS[0], USER_ACCOUNTS[1]));
+}
+
+#[test]
+fn success_get_token_uris_page() {
+    let system = System::new();
+    system.init_logger();
+
+    let mtoken = Program::mtoken(&system);
+
+    for tx_id in 0..3 {
+        mtoken.create(
+            tx_id,
+            USER_ACCOUNTS[0],
+            1000,
+            format!("https://example{tx_id}.com"),
+            false,
+            false,
+        );
+    }
+
+    let state: MTokenState = mtoken.read_state().expect("Unable to read `MTokenState`.");
+    let mt_logic_id: [u8; 32] = state.mt_logic_id.into();
+    let mt_logic = system.get_program(mt_logic_id);
+
+    let first_token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2);
+    let res = mt_logic.send(
+        USER_ACCOUNTS[0],
+        MTLogicAction::GetTokenUris {
+            after: Some(first_token_id),
+            limit: 5,
+        },
+    );
+    assert!(!res.main_failed());
+
+    let token_uris = res
+        .log()
+        .iter()
+        .find_map(|log| {
+            if let Ok(MTLogicEvent::TokenUris(token_uris)) =
+                MTLogicEvent::decode(&mut log.payload())
+            {
+                Some(token_uris)
+            } else {
+                None
+            }
+        })
+        .expect("`MTLogicEvent::TokenUris` not found in reply.");
+
+    let second_token_id: TokenId = 2 << (mem::size_of::<TokenId>() * 8 / 2);
+    let third_token_id: TokenId = 3 << (mem::size_of::<TokenId>() * 8 / 2);
+    assert_eq!(
+        token_uris,
+        vec![
+            (second_token_id, String::from("https://example1.com")),
+            (third_token_id, String::from("https://example2.com")),
+        ]
+    );
+}
+
+#[test]
+fn success_update_and_freeze_metadata() {
+    let system = System::new();
+    system.init_logger();
+
+    let mut tx_id = 0;
+    let token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2);
+    let metadata = TokenMetadata {
+        name: String::from("Gold"),
+        symbol: String::from("GLD"),
+        decimals: 18,
+    };
+    let mtoken = Program::mtoken(&system);
+
+    assert!(mtoken.get_token_metadata(token_id).is_none());
+
+    mtoken.create_with_metadata(
+        tx_id,
+        USER_ACCOUNTS[0],
+        1000,
+        String::from("https://example.com"),
+        metadata.clone(),
+        false,
+        false,
+    );
+    assert_eq!(
+        mtoken.get_token_metadata(token_id),
+        Some((metadata.clone(), String::from("https://example.com"), false))
+    );
+    tx_id += 1;
+
+    mtoken.update_token_uri(
+        tx_id,
+        USER_ACCOUNTS[1],
+        token_id,
+        String::from("https://example1.com"),
+        true,
+    );
+    tx_id += 1;
+
+    mtoken.update_token_uri(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        String::from("https://example1.com"),
+        false,
+    );
+    assert_eq!(
+        mtoken.get_token_metadata(token_id),
+        Some((
+            metadata.clone(),
+            String::from("https://example1.com"),
+            false
+        ))
+    );
+    tx_id += 1;
+
+    mtoken.freeze_metadata(tx_id, USER_ACCOUNTS[1], token_id, true);
+    tx_id += 1;
+
+    mtoken.freeze_metadata(tx_id, USER_ACCOUNTS[0], token_id, false);
+    tx_id += 1;
+
+    mtoken.update_token_uri(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        String::from("https://example2.com"),
+        true,
+    );
+    assert_eq!(
+        mtoken.get_token_metadata(token_id),
+        Some((metadata, String::from("https://example1.com"), true))
+    );
+}
+
+#[test]
+fn success_tokens_of() {
+    let system = System::new();
+    system.init_logger();
+
+    let mut tx_id = 0;
+    let initial_amount = 1000000;
+    let first_token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2);
+    let second_token_id: TokenId = 2 << (mem::size_of::<TokenId>() * 8 / 2);
+    let nft_id: TokenId = 3 << (mem::size_of::<TokenId>() * 8 / 2) | NFT_BIT | 1;
+    let mtoken = Program::mtoken(&system);
+
+    assert!(mtoken.tokens_of(USER_ACCOUNTS[0], 0, 10).is_empty());
+
+    for is_nft in [false, false, true] {
+        mtoken.create(
+            tx_id,
+            USER_ACCOUNTS[0],
+            initial_amount,
+            String::from("https://example.com"),
+            is_nft,
+            false,
+        );
+        tx_id += 1;
+    }
+
+    mtoken.mint_batch_nft(
+        tx_id,
+        USER_ACCOUNTS[0],
+        nft_id & !1,
+        vec![USER_ACCOUNTS[0]],
+        false,
+    );
+    tx_id += 1;
+
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[0],
+        second_token_id,
+        USER_ACCOUNTS[1],
+        initial_amount,
+        false,
+    );
+
+    assert_eq!(
+        mtoken.tokens_of(USER_ACCOUNTS[0], 0, 10),
+        vec![first_token_id, nft_id]
+    );
+    assert_eq!(
+        mtoken.tokens_of(USER_ACCOUNTS[0], 0, 1),
+        vec![first_token_id]
+    );
+    assert_eq!(mtoken.tokens_of(USER_ACCOUNTS[0], 1, 1), vec![nft_id]);
+    assert_eq!(
+        mtoken.tokens_of(USER_ACCOUNTS[1], 0, 10),
+        vec![second_token_id]
+    );
+}
+
+#[test]
+fn success_holders_of() {
+    let system = System::new();
+    system.init_logger();
+
+    let mut tx_id = 0;
+    let initial_amount = 1000000;
+    let token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2);
+    let mtoken = Program::mtoken(&system);
+
+    assert_eq!(mtoken.holders_of(token_id, 0, 10), (vec![], 0));
+
+    mtoken.create(
+        tx_id,
+        USER_ACCOUNTS[0],
+        initial_amount,
+        String::from("https://example.com"),
+        false,
+        false,
+    );
+    tx_id += 1;
+
+    for to in [USER_ACCOUNTS[1], USER_ACCOUNTS[2]] {
+        mtoken.transfer(tx_id, USER_ACCOUNTS[0], token_id, to, 1000, false);
+        tx_id += 1;
+    }
+
+    let (mut holders, total) = mtoken.holders_of(token_id, 0, 10);
+    holders.sort_unstable();
+    assert_eq!(total, 3);
+    assert_eq!(
+        holders,
+        vec![
+            (ActorId::from(USER_ACCOUNTS[0]), initial_amount - 2000),
+            (ActorId::from(USER_ACCOUNTS[1]), 1000),
+            (ActorId::from(USER_ACCOUNTS[2]), 1000),
+        ]
+    );
+
+    let (first_page, total) = mtoken.holders_of(token_id, 0, 2);
+    assert_eq!((first_page.len(), total), (2, 3));
+    let (second_page, _) = mtoken.holders_of(token_id, 2, 2);
+    assert_eq!(second_page.len(), 1);
+    assert!(!first_page.contains(&second_page[0]));
+
+    // A holder with zero balance is no longer counted
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[2],
+        token_id,
+        USER_ACCOUNTS[1],
+        1000,
+        false,
+    );
+
+    let (holders, total) = mtoken.holders_of(token_id, 0, 10);
+    assert_eq!(total, 2);
+    assert!(!holders.contains(&(USER_ACCOUNTS[2].into(), 0)));
+    assert!(holders.contains(&(USER_ACCOUNTS[1].into(), 2000)));
+}
+
+#[test]
+fn success_balance_of_at() {
+    let system = System::new();
+    system.init_logger();
+
+    let mut tx_id = 0;
+    let initial_amount = 1000000;
+    let token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2);
+    let mtoken = Program::mtoken(&system);
+
+    mtoken.create(
+        tx_id,
+        USER_ACCOUNTS[0],
+        initial_amount,
+        String::from("https://example.com"),
+        false,
+        false,
+    );
+    tx_id += 1;
+
+    // Only the creator or the admin can take a snapshot
+    mtoken.snapshot(tx_id, USER_ACCOUNTS[1], token_id, 1, true);
+    tx_id += 1;
+    assert_eq!(mtoken.balance_of_at(token_id, USER_ACCOUNTS[0], 1), None);
+
+    mtoken.snapshot(tx_id, USER_ACCOUNTS[0], token_id, 1, false);
+    tx_id += 1;
+
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        USER_ACCOUNTS[1],
+        1000,
+        false,
+    );
+    tx_id += 1;
+
+    // `USER_ACCOUNTS[2]` storage is created after the first snapshot
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        USER_ACCOUNTS[2],
+        1000,
+        false,
+    );
+    tx_id += 1;
+
+    mtoken.snapshot(tx_id, ROOT_ACCOUNT, token_id, 2, false);
+    tx_id += 1;
+
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[1],
+        token_id,
+        USER_ACCOUNTS[2],
+        500,
+        false,
+    );
+
+    assert_eq!(
+        mtoken.balance_of_at(token_id, USER_ACCOUNTS[0], 1),
+        Some(initial_amount)
+    );
+    assert_eq!(mtoken.balance_of_at(token_id, USER_ACCOUNTS[1], 1), Some(0));
+    assert_eq!(mtoken.balance_of_at(token_id, USER_ACCOUNTS[2], 1), Some(0));
+    assert_eq!(
+        mtoken.balance_of_at(token_id, USER_ACCOUNTS[0], 2),
+        Some(initial_amount - 2000)
+    );
+    assert_eq!(
+        mtoken.balance_of_at(token_id, USER_ACCOUNTS[1], 2),
+        Some(1000)
+    );
+    assert_eq!(
+        mtoken.balance_of_at(token_id, USER_ACCOUNTS[2], 2),
+        Some(1000)
+    );
+    assert_eq!(mtoken.balance_of_at(token_id, USER_ACCOUNTS[2], 3), None);
+    assert_eq!(mtoken.get_balance(token_id, USER_ACCOUNTS[2]), 1500);
+}
+
+#[test]
+fn success_freeze_token_and_account() {
+    let system = System::new();
+    system.init_logger();
+
+    let mut tx_id = 0;
+    let initial_amount = 1000000;
+    let token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2);
+    let mtoken = Program::mtoken(&system);
+
+    mtoken.create(
+        tx_id,
+        USER_ACCOUNTS[0],
+        initial_amount,
+        String::from("https://example.com"),
+        false,
+        false,
+    );
+    tx_id += 1;
+
+    // Only the creator can freeze
+    mtoken.freeze_token(tx_id, USER_ACCOUNTS[1], token_id, true, true);
+    tx_id += 1;
+    mtoken.freeze_account(
+        tx_id,
+        USER_ACCOUNTS[1],
+        token_id,
+        USER_ACCOUNTS[2],
+        true,
+        true,
+    );
+    tx_id += 1;
+
+    mtoken.freeze_account(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        USER_ACCOUNTS[1],
+        true,
+        false,
+    );
+    tx_id += 1;
+
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        USER_ACCOUNTS[1],
+        1000,
+        true,
+    );
+    tx_id += 1;
+    mtoken.mint_batch_ft(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        vec![USER_ACCOUNTS[2], USER_ACCOUNTS[1]],
+        vec![1000, 1000],
+        true,
+    );
+    tx_id += 1;
+    assert_eq!(mtoken.get_balance(token_id, USER_ACCOUNTS[2]), 0);
+
+    mtoken.freeze_account(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        USER_ACCOUNTS[1],
+        false,
+        false,
+    );
+    tx_id += 1;
+
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        USER_ACCOUNTS[1],
+        1000,
+        false,
+    );
+    tx_id += 1;
+
+    mtoken.freeze_token(tx_id, USER_ACCOUNTS[0], token_id, true, false);
+    tx_id += 1;
+
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[1],
+        token_id,
+        USER_ACCOUNTS[2],
+        1000,
+        true,
+    );
+    tx_id += 1;
+    mtoken.burn_batch_ft(
+        tx_id,
+        USER_ACCOUNTS[1],
+        token_id,
+        vec![USER_ACCOUNTS[1]],
+        vec![1000],
+        true,
+    );
+    tx_id += 1;
+
+    mtoken.freeze_token(tx_id, USER_ACCOUNTS[0], token_id, false, false);
+    tx_id += 1;
+
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[1],
+        token_id,
+        USER_ACCOUNTS[2],
+        1000,
+        false,
+    );
+    assert_eq!(mtoken.get_balance(token_id, USER_ACCOUNTS[2]), 1000);
+}
+
+#[test]
+fn success_transfer_with_allowlist() {
+    let system = System::new();
+    system.init_logger();
+
+    let mut tx_id = 0;
+    let initial_amount = 1000000;
+    let token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2);
+    let mtoken = Program::mtoken(&system);
+
+    mtoken.create(
+        tx_id,
+        USER_ACCOUNTS[0],
+        initial_amount,
+        String::from("https://example.com"),
+        false,
+        false,
+    );
+    tx_id += 1;
+
+    // Only the creator can manage the allowlist
+    mtoken.set_allowlist_mode(tx_id, USER_ACCOUNTS[1], token_id, true, true);
+    tx_id += 1;
+    mtoken.update_allowlist(
+        tx_id,
+        USER_ACCOUNTS[1],
+        token_id,
+        vec![USER_ACCOUNTS[1]],
+        true,
+        true,
+    );
+    tx_id += 1;
+
+    mtoken.set_allowlist_mode(tx_id, USER_ACCOUNTS[0], token_id, true, false);
+    tx_id += 1;
+
+    // Both parties must be on the allowlist
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        USER_ACCOUNTS[1],
+        1000,
+        true,
+    );
+    tx_id += 1;
+
+    mtoken.update_allowlist(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        vec![USER_ACCOUNTS[0], USER_ACCOUNTS[1]],
+        true,
+        false,
+    );
+    tx_id += 1;
+
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        USER_ACCOUNTS[1],
+        1000,
+        false,
+    );
+    tx_id += 1;
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[1],
+        token_id,
+        USER_ACCOUNTS[2],
+        1000,
+        true,
+    );
+    tx_id += 1;
+    mtoken.mint_batch_ft(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        vec![USER_ACCOUNTS[2]],
+        vec![1000],
+        true,
+    );
+    tx_id += 1;
+
+    mtoken.set_allowlist_mode(tx_id, USER_ACCOUNTS[0], token_id, false, false);
+    tx_id += 1;
+
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[1],
+        token_id,
+        USER_ACCOUNTS[2],
+        1000,
+        false,
+    );
+    assert_eq!(mtoken.get_balance(token_id, USER_ACCOUNTS[1]), 0);
+    assert_eq!(mtoken.get_balance(token_id, USER_ACCOUNTS[2]), 1000);
+}
+
+#[test]
+fn success_transfer_with_fee() {
+    let system = System::new();
+    system.init_logger();
+
+    let mut tx_id = 0;
+    let initial_amount = 1000000;
+    let token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2);
+    let treasury = USER_ACCOUNTS[2];
+    let mtoken = Program::mtoken(&system);
+
+    mtoken.create(
+        tx_id,
+        USER_ACCOUNTS[0],
+        initial_amount,
+        String::from("https://example.com"),
+        false,
+        false,
+    );
+    tx_id += 1;
+
+    // Only the creator can set a valid fee
+    let fee = TransferFee {
+        fee: Fee::BasisPoints(100),
+        treasury: treasury.into(),
+    };
+    mtoken.set_transfer_fee(tx_id, USER_ACCOUNTS[1], token_id, Some(fee), true);
+    tx_id += 1;
+    mtoken.set_transfer_fee(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        Some(TransferFee {
+            fee: Fee::BasisPoints(10001),
+            ..fee
+        }),
+        true,
+    );
+    tx_id += 1;
+
+    mtoken.set_transfer_fee(tx_id, USER_ACCOUNTS[0], token_id, Some(fee), false);
+    tx_id += 1;
+
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        USER_ACCOUNTS[1],
+        1000,
+        false,
+    );
+    tx_id += 1;
+
+    assert_eq!(
+        mtoken.get_balance(token_id, USER_ACCOUNTS[0]),
+        initial_amount - 1000
+    );
+    assert_eq!(mtoken.get_balance(token_id, USER_ACCOUNTS[1]), 990);
+    assert_eq!(mtoken.get_balance(token_id, treasury), 10);
+
+    // The flat fee must be covered by the transferred amount
+    mtoken.set_transfer_fee(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        Some(TransferFee {
+            fee: Fee::Flat(500),
+            ..fee
+        }),
+        false,
+    );
+    tx_id += 1;
+
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[1],
+        token_id,
+        USER_ACCOUNTS[0],
+        400,
+        true,
+    );
+    tx_id += 1;
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[1],
+        token_id,
+        USER_ACCOUNTS[0],
+        990,
+        false,
+    );
+    tx_id += 1;
+
+    assert_eq!(mtoken.get_balance(token_id, USER_ACCOUNTS[1]), 0);
+    assert_eq!(
+        mtoken.get_balance(token_id, USER_ACCOUNTS[0]),
+        initial_amount - 1000 + 490
+    );
+    assert_eq!(mtoken.get_balance(token_id, treasury), 510);
+
+    // Failed transfer leaves all balances untouched
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[1],
+        token_id,
+        USER_ACCOUNTS[0],
+        1000,
+        true,
+    );
+    assert_eq!(mtoken.get_balance(token_id, treasury), 510);
+}
+
+#[test]
+fn success_deposit_and_withdraw_native_value() {
+    let system = System::new();
+    system.init_logger();
+
+    let mut tx_id = 0;
+    let value = 1000000;
+    let mtoken = Program::mtoken(&system);
+
+    system.mint_to(USER_ACCOUNTS[0], value);
+
+    mtoken.deposit(tx_id, USER_ACCOUNTS[0], value, false);
+    tx_id += 1;
+    assert_eq!(mtoken.get_balance(NATIVE_TOKEN_ID, USER_ACCOUNTS[0]), value);
+    assert_eq!(system.balance_of(USER_ACCOUNTS[0]), 0);
+
+    // Native tokens can't be minted or burned directly
+    mtoken.mint_batch_ft(
+        tx_id,
+        USER_ACCOUNTS[0],
+        NATIVE_TOKEN_ID,
+        vec![USER_ACCOUNTS[0]],
+        vec![value],
+        true,
+    );
+    tx_id += 1;
+    mtoken.burn_batch_ft(
+        tx_id,
+        USER_ACCOUNTS[0],
+        NATIVE_TOKEN_ID,
+        vec![USER_ACCOUNTS[0]],
+        vec![value],
+        true,
+    );
+    tx_id += 1;
+
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[0],
+        NATIVE_TOKEN_ID,
+        USER_ACCOUNTS[1],
+        value / 2,
+        false,
+    );
+    tx_id += 1;
+
+    mtoken.withdraw(tx_id, USER_ACCOUNTS[0], value, true);
+    tx_id += 1;
+
+    // Value attached to a withdrawal is returned with the withdrawn value
+    let attached_value = 1000;
+    system.mint_to(USER_ACCOUNTS[0], attached_value);
+    let res = mtoken.send_with_value(
+        USER_ACCOUNTS[0],
+        MTokenAction::Withdraw {
+            transaction_id: tx_id,
+            amount: value / 2,
+        },
+        attached_value,
+    );
+    assert!(res.contains(&(USER_ACCOUNTS[0], MTokenEvent::Ok.encode())));
+    mtoken.withdraw(tx_id, USER_ACCOUNTS[1], value / 2, false);
+
+    assert_eq!(mtoken.get_balance(NATIVE_TOKEN_ID, USER_ACCOUNTS[0]), 0);
+    assert_eq!(mtoken.get_balance(NATIVE_TOKEN_ID, USER_ACCOUNTS[1]), 0);
+    assert_eq!(
+        system.balance_of(USER_ACCOUNTS[0]),
+        value / 2 + attached_value
+    );
+    assert_eq!(system.balance_of(USER_ACCOUNTS[1]), value / 2);
+}
+
+#[test]
+fn success_swap() {
+    let system = System::new();
+    system.init_logger();
+
+    let mut tx_id = 0;
+    let initial_amount = 1000000;
+    let token_a: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2);
+    let token_b: TokenId = 2 << (mem::size_of::<TokenId>() * 8 / 2);
+    let mtoken = Program::mtoken(&system);
+
+    mtoken.create(
+        tx_id,
+        USER_ACCOUNTS[0],
+        initial_amount,
+        String::from("https://example.com"),
+        false,
+        false,
+    );
+    tx_id += 1;
+    mtoken.create(
+        tx_id,
+        USER_ACCOUNTS[1],
+        initial_amount,
+        String::from("https://example.com"),
+        false,
+        false,
+    );
+    tx_id += 1;
+
+    let legs = |amount_a: u128, amount_b: u128| {
+        vec![
+            SwapLeg {
+                from: USER_ACCOUNTS[0].into(),
+                to: USER_ACCOUNTS[1].into(),
+                token_id: token_a,
+                amount: amount_a,
+            },
+            SwapLeg {
+                from: USER_ACCOUNTS[1].into(),
+                to: USER_ACCOUNTS[0].into(),
+                token_id: token_b,
+                amount: amount_b,
+            },
+        ]
+    };
+
+    // Only a party can propose a swap
+    mtoken.propose_swap(tx_id, USER_ACCOUNTS[2], legs(100, 200), 1000, 0, true);
+    tx_id += 1;
+
+    // The settlement is compensated if a party doesn't have enough tokens
+    mtoken.propose_swap(
+        tx_id,
+        USER_ACCOUNTS[0],
+        legs(100, initial_amount + 1),
+        1000,
+        1,
+        false,
+    );
+    tx_id += 1;
+    mtoken.confirm_swap(tx_id, USER_ACCOUNTS[1], 1, true);
+    tx_id += 1;
+
+    // Offers of the proposer stay escrowed until the swap is cancelled
+    assert_eq!(
+        mtoken.get_balance(token_a, USER_ACCOUNTS[0]),
+        initial_amount - 100
+    );
+    mtoken.cancel_swap(tx_id, USER_ACCOUNTS[0], 1, false);
+    tx_id += 1;
+
+    assert_eq!(
+        mtoken.get_balance(token_a, USER_ACCOUNTS[0]),
+        initial_amount
+    );
+    assert_eq!(mtoken.get_balance(token_a, USER_ACCOUNTS[1]), 0);
+    assert_eq!(
+        mtoken.get_balance(token_b, USER_ACCOUNTS[1]),
+        initial_amount
+    );
+
+    mtoken.propose_swap(tx_id, USER_ACCOUNTS[0], legs(100, 200), 1000, 2, false);
+    tx_id += 1;
+    // The proposer can't confirm and escrow its offers twice
+    mtoken.confirm_swap(tx_id, USER_ACCOUNTS[0], 2, true);
+    tx_id += 1;
+    mtoken.confirm_swap(tx_id, USER_ACCOUNTS[2], 2, true);
+    tx_id += 1;
+    mtoken.confirm_swap(tx_id, USER_ACCOUNTS[1], 2, false);
+    tx_id += 1;
+
+    assert_eq!(
+        mtoken.get_balance(token_a, USER_ACCOUNTS[0]),
+        initial_amount - 100
+    );
+    assert_eq!(mtoken.get_balance(token_a, USER_ACCOUNTS[1]), 100);
+    assert_eq!(
+        mtoken.get_balance(token_b, USER_ACCOUNTS[1]),
+        initial_amount - 200
+    );
+    assert_eq!(mtoken.get_balance(token_b, USER_ACCOUNTS[0]), 200);
+
+    // A settled swap can't be confirmed again
+    mtoken.confirm_swap(tx_id, USER_ACCOUNTS[0], 2, true);
+    tx_id += 1;
+
+    // A cancelled swap can't be confirmed
+    mtoken.propose_swap(tx_id, USER_ACCOUNTS[0], legs(100, 200), 1000, 3, false);
+    tx_id += 1;
+    mtoken.cancel_swap(tx_id, USER_ACCOUNTS[2], 3, true);
+    tx_id += 1;
+    mtoken.cancel_swap(tx_id, USER_ACCOUNTS[1], 3, false);
+    tx_id += 1;
+    mtoken.confirm_swap(tx_id, USER_ACCOUNTS[1], 3, true);
+    tx_id += 1;
+
+    assert_eq!(
+        mtoken.get_balance(token_a, USER_ACCOUNTS[0]),
+        initial_amount - 100
+    );
+
+    // An expired swap can't be confirmed, anyone can release its offers
+    mtoken.propose_swap(tx_id, USER_ACCOUNTS[0], legs(100, 200), 1000, 4, false);
+    tx_id += 1;
+    assert_eq!(
+        mtoken.get_balance(token_a, USER_ACCOUNTS[0]),
+        initial_amount - 200
+    );
+    system.spend_blocks(1000);
+    mtoken.confirm_swap(tx_id, USER_ACCOUNTS[1], 4, true);
+    tx_id += 1;
+    mtoken.cancel_swap(tx_id, USER_ACCOUNTS[2], 4, false);
+
+    assert_eq!(
+        mtoken.get_balance(token_a, USER_ACCOUNTS[0]),
+        initial_amount - 100
+    );
+    assert_eq!(mtoken.get_balance(token_a, USER_ACCOUNTS[1]), 100);
+    assert_eq!(mtoken.get_balance(token_b, USER_ACCOUNTS[0]), 200);
+}
+
+#[test]
+fn fail_settle_swap_twice() {
+    let system = System::new();
+    system.init_logger();
+
+    let mut tx_id = 0;
+    let initial_amount = 1000000;
+    let token_a: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2);
+    let token_b: TokenId = 2 << (mem::size_of::<TokenId>() * 8 / 2);
+    let mtoken = Program::mtoken(&system);
+
+    mtoken.create(
+        tx_id,
+        USER_ACCOUNTS[0],
+        initial_amount,
+        String::from("https://example.com"),
+        false,
+        false,
+    );
+    tx_id += 1;
+    mtoken.create(
+        tx_id,
+        USER_ACCOUNTS[1],
+        initial_amount,
+        String::from("https://example.com"),
+        false,
+        false,
+    );
+    tx_id += 1;
+
+    let legs = vec![
+        SwapLeg {
+            from: USER_ACCOUNTS[0].into(),
+            to: USER_ACCOUNTS[1].into(),
+            token_id: token_a,
+            amount: 100,
+        },
+        SwapLeg {
+            from: USER_ACCOUNTS[1].into(),
+            to: USER_ACCOUNTS[0].into(),
+            token_id: token_b,
+            amount: initial_amount + 1,
+        },
+    ];
+
+    mtoken.propose_swap(tx_id, USER_ACCOUNTS[0], legs, 1000, 1, false);
+    tx_id += 1;
+
+    // The compensated swap is kept, so it can be confirmed again
+    mtoken.confirm_swap(tx_id, USER_ACCOUNTS[1], 1, true);
+    tx_id += 1;
+    mtoken.mint_batch_ft(
+        tx_id,
+        USER_ACCOUNTS[1],
+        token_b,
+        vec![USER_ACCOUNTS[1]],
+        vec![1],
+        false,
+    );
+    tx_id += 1;
+    mtoken.confirm_swap(tx_id, USER_ACCOUNTS[1], 1, false);
+    tx_id += 1;
+
+    // The second confirmation of the fully confirmed swap doesn't settle it again
+    mtoken.confirm_swap(tx_id, USER_ACCOUNTS[1], 1, true);
+    tx_id += 1;
+    mtoken.confirm_swap(tx_id, USER_ACCOUNTS[0], 1, true);
+
+    assert_eq!(
+        mtoken.get_balance(token_a, USER_ACCOUNTS[0]),
+        initial_amount - 100
+    );
+    assert_eq!(mtoken.get_balance(token_a, USER_ACCOUNTS[1]), 100);
+    assert_eq!(mtoken.get_balance(token_b, USER_ACCOUNTS[1]), 0);
+    assert_eq!(
+        mtoken.get_balance(token_b, USER_ACCOUNTS[0]),
+        initial_amount + 1
+    );
+}
+
+#[test]
+fn success_mint_vested() {
+    let system = System::new();
+    system.init_logger();
+
+    let mut tx_id = 0;
+    let token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2);
+    let mtoken = Program::mtoken(&system);
+
+    mtoken.create(
+        tx_id,
+        USER_ACCOUNTS[0],
+        0,
+        String::from("https://example.com"),
+        false,
+        false,
+    );
+    tx_id += 1;
+
+    // Only the creator can mint vested tokens with a valid schedule
+    mtoken.mint_vested(
+        tx_id,
+        USER_ACCOUNTS[1],
+        token_id,
+        USER_ACCOUNTS[1],
+        1000,
+        10,
+        100,
+        true,
+    );
+    tx_id += 1;
+    mtoken.mint_vested(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        USER_ACCOUNTS[1],
+        1000,
+        101,
+        100,
+        true,
+    );
+    tx_id += 1;
+    mtoken.mint_vested(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        USER_ACCOUNTS[1],
+        1000,
+        10,
+        100,
+        false,
+    );
+    tx_id += 1;
+
+    assert_eq!(mtoken.get_balance(token_id, USER_ACCOUNTS[1]), 1000);
+
+    // Nothing is released before the cliff
+    mtoken.transfer(tx_id, USER_ACCOUNTS[1], token_id, USER_ACCOUNTS[2], 1, true);
+    tx_id += 1;
+    mtoken.burn_batch_ft(
+        tx_id,
+        USER_ACCOUNTS[1],
+        token_id,
+        vec![USER_ACCOUNTS[1]],
+        vec![1],
+        true,
+    );
+    tx_id += 1;
+
+    // About a half is released in the middle of the schedule
+    system.spend_blocks(50);
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[1],
+        token_id,
+        USER_ACCOUNTS[2],
+        400,
+        false,
+    );
+    tx_id += 1;
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[1],
+        token_id,
+        USER_ACCOUNTS[2],
+        600,
+        true,
+    );
+    tx_id += 1;
+
+    // Everything is released after the schedule ends
+    system.spend_blocks(100);
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[1],
+        token_id,
+        USER_ACCOUNTS[2],
+        600,
+        false,
+    );
+
+    assert_eq!(mtoken.get_balance(token_id, USER_ACCOUNTS[1]), 0);
+    assert_eq!(mtoken.get_balance(token_id, USER_ACCOUNTS[2]), 1000);
+}
+
+#[test]
+fn success_schedule_transfer() {
+    let system = System::new();
+    system.init_logger();
+
+    let mut tx_id = 0;
+    let initial_amount = 1000000;
+    let token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2);
+    let mtoken = Program::mtoken(&system);
+
+    mtoken.create(
+        tx_id,
+        USER_ACCOUNTS[0],
+        initial_amount,
+        String::from("https://example.com"),
+        false,
+        false,
+    );
+    tx_id += 1;
+
+    // The transfer must be scheduled in the future with available tokens
+    let at_block = system.block_height();
+    mtoken.schedule_transfer(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        USER_ACCOUNTS[1],
+        100,
+        at_block,
+        0,
+        true,
+    );
+    tx_id += 1;
+    let at_block = system.block_height() + 10;
+    mtoken.schedule_transfer(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        USER_ACCOUNTS[1],
+        initial_amount + 1,
+        at_block,
+        0,
+        true,
+    );
+    tx_id += 1;
+
+    // The gas reservation doesn't live long enough for distant transfers
+    mtoken.schedule_transfer(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        USER_ACCOUNTS[1],
+        100,
+        system.block_height() + 1000,
+        0,
+        true,
+    );
+    tx_id += 1;
+
+    // The amount is escrowed until the transfer is executed
+    mtoken.schedule_transfer(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        USER_ACCOUNTS[1],
+        100,
+        at_block,
+        1,
+        false,
+    );
+    tx_id += 1;
+
+    assert_eq!(
+        mtoken.get_balance(token_id, USER_ACCOUNTS[0]),
+        initial_amount - 100
+    );
+    assert_eq!(mtoken.get_balance(token_id, USER_ACCOUNTS[1]), 0);
+
+    system.spend_blocks(20);
+
+    assert_eq!(mtoken.get_balance(token_id, USER_ACCOUNTS[1]), 100);
+
+    // Only the sender can cancel the transfer before it's executed
+    let at_block = system.block_height() + 10;
+    mtoken.schedule_transfer(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        USER_ACCOUNTS[1],
+        100,
+        at_block,
+        2,
+        false,
+    );
+    tx_id += 1;
+    mtoken.cancel_scheduled_transfer(tx_id, USER_ACCOUNTS[1], 2, true);
+    tx_id += 1;
+    mtoken.cancel_scheduled_transfer(tx_id, USER_ACCOUNTS[0], 2, false);
+    tx_id += 1;
+    mtoken.cancel_scheduled_transfer(tx_id, USER_ACCOUNTS[0], 2, true);
+
+    assert_eq!(
+        mtoken.get_balance(token_id, USER_ACCOUNTS[0]),
+        initial_amount - 100
+    );
+
+    system.spend_blocks(20);
+
+    assert_eq!(mtoken.get_balance(token_id, USER_ACCOUNTS[1]), 100);
+}
+
+fn keypair(seed: u8) -> Keypair {
+    MiniSecretKey::from_bytes(&[seed; 32])
+        .expect("Invalid secret key.")
+        .expand_to_keypair(ExpansionMode::Ed25519)
+}
+
+#[test]
+fn success_relay_message() {
+    let system = System::new();
+    system.init_logger();
+
+    let initial_amount = 1000000;
+    let token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2);
+    let signer = keypair(1);
+    let signer_id = ActorId::new(signer.public.to_bytes());
+    let relayer = USER_ACCOUNTS[2];
+    let deadline = system.block_height() + 100;
+    let mtoken = Program::mtoken(&system);
+
+    let create = LogicAction::Create {
+        initial_amount,
+        uri: String::from("https://example.com"),
+        metadata: Default::default(),
+        is_nft: false,
+        is_soulbound: false,
+        is_edition: false,
+        royalty: None,
+    };
+    let transfer = LogicAction::Transfer {
+        token_id,
+        sender: signer_id,
+        recipient: USER_ACCOUNTS[1].into(),
+        amount: 100,
+    };
+
+    // The signature must be made by the signer over the next nonce
+    mtoken.relay_message(relayer, &signer, 1, create.clone(), deadline, true);
+    mtoken.relay_message(relayer, &signer, 0, create, deadline, false);
+    mtoken.relay_message(relayer, &signer, 1, transfer.clone(), deadline, false);
+
+    assert_eq!(mtoken.get_balance(token_id, USER_ACCOUNTS[1]), 100);
+
+    // The relayed transaction isn't executed twice
+    mtoken.relay_message(relayer, &signer, 1, transfer.clone(), deadline, false);
+
+    assert_eq!(mtoken.get_balance(token_id, USER_ACCOUNTS[1]), 100);
+
+    let state: MTokenState = mtoken.read_state().expect("Unable to read `MTokenState`.");
+    let relay_nonce = state
+        .relay_nonces
+        .into_iter()
+        .find_map(|(account, nonce)| (account == signer_id).then_some(nonce));
+    assert_eq!(relay_nonce, Some(2));
+
+    // The signature of another account is rejected
+    let other = keypair(2);
+    let message = MTokenAction::RelayMessage {
+        signer: signer_id,
+        transaction_id: 2,
+        payload: transfer.clone(),
+        signature: other
+            .sign_simple(
+                SIGNING_CONTEXT,
+                &relay_message_data(
+                    &ActorId::from_slice(mtoken.id().as_ref()).expect("Invalid program id."),
+                    &signer_id,
+                    2,
+                    &transfer,
+                    deadline,
+                ),
+            )
+            .to_bytes(),
+        deadline,
+    };
+    mtoken.send_message_and_check_res(relayer, message, true);
+
+    // The expired signature is rejected
+    system.spend_blocks(200);
+    mtoken.relay_message(relayer, &signer, 2, transfer, deadline, true);
+
+    assert_eq!(mtoken.get_balance(token_id, USER_ACCOUNTS[1]), 100);
+}
+
+#[test]
+fn success_relay_message_after_direct_message() {
+    let system = System::new();
+    system.init_logger();
+
+    let initial_amount = 1000000;
+    let token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2);
+    let signer = keypair(1);
+    let signer_id = ActorId::new(signer.public.to_bytes());
+    let relayer = USER_ACCOUNTS[2];
+    let deadline = system.block_height() + 100;
+    let mtoken = Program::mtoken(&system);
+
+    // The signer sends the transaction 0 by itself
+    let res = mtoken.send(
+        signer.public.to_bytes(),
+        MTokenAction::Message {
+            transaction_id: 0,
+            payload: LogicAction::Create {
+                initial_amount,
+                uri: String::from("https://example.com"),
+                metadata: Default::default(),
+                is_nft: false,
+                is_soulbound: false,
+                is_edition: false,
+                royalty: None,
+            },
+        },
+    );
+    assert!(res.contains(&(signer.public.to_bytes(), MTokenEvent::Ok.encode())));
+
+    // The relay nonce 0 isn't taken for a retry of that transaction
+    let transfer = LogicAction::Transfer {
+        token_id,
+        sender: signer_id,
+        recipient: USER_ACCOUNTS[1].into(),
+        amount: 100,
+    };
+    mtoken.relay_message(relayer, &signer, 0, transfer, deadline, false);
+
+    assert_eq!(mtoken.get_balance(token_id, USER_ACCOUNTS[1]), 100);
+
+    let state: MTokenState = mtoken.read_state().expect("Unable to read `MTokenState`.");
+    let relay_nonce = state
+        .relay_nonces
+        .into_iter()
+        .find_map(|(account, nonce)| (account == signer_id).then_some(nonce));
+    assert_eq!(relay_nonce, Some(1));
+}
+
+#[test]
+fn success_permit() {
+    let system = System::new();
+    system.init_logger();
+
+    let initial_amount = 1000000;
+    let token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2);
+    let owner = keypair(1);
+    let owner_id = ActorId::new(owner.public.to_bytes());
+    let spender = USER_ACCOUNTS[1];
+    let submitter = USER_ACCOUNTS[2];
+    let deadline = system.block_height() + 100;
+    let mtoken = Program::mtoken(&system);
+
+    let create = LogicAction::Create {
+        initial_amount,
+        uri: String::from("https://example.com"),
+        metadata: Default::default(),
+        is_nft: false,
+        is_soulbound: false,
+        is_edition: false,
+        royalty: None,
+    };
+    mtoken.relay_message(submitter, &owner, 0, create, deadline, false);
+
+    // The permit must be signed over the current nonce
+    mtoken.permit(0, submitter, &owner, spender, true, 1, deadline, true);
+    mtoken.permit(1, submitter, &owner, spender, true, 0, deadline, false);
+
+    let transfer = LogicAction::Transfer {
+        token_id,
+        sender: owner_id,
+        recipient: spender.into(),
+        amount: 100,
+    };
+    mtoken.send_message_and_check_res(
+        spender,
+        MTokenAction::Message {
+            transaction_id: 0,
+            payload: transfer,
+        },
+        false,
+    );
+
+    assert_eq!(mtoken.get_balance(token_id, spender), 100);
+
+    // The used permit can't be replayed
+    mtoken.permit(2, submitter, &owner, spender, false, 0, deadline, true);
+
+    // The expired permit is rejected
+    system.spend_blocks(200);
+    mtoken.permit(3, submitter, &owner, spender, false, 1, deadline, true);
+
+    mtoken.permit(
+        4,
+        submitter,
+        &owner,
+        spender,
+        false,
+        1,
+        system.block_height() + 100,
+        false,
+    );
+    mtoken.send_message_and_check_res(
+        spender,
+        MTokenAction::Message {
+            transaction_id: 1,
+            payload: LogicAction::Transfer {
+                token_id,
+                sender: owner_id,
+                recipient: spender.into(),
+                amount: 100,
+            },
+        },
+        true,
+    );
+
+    assert_eq!(mtoken.get_balance(token_id, spender), 100);
+}
+
+#[test]
+fn success_multicall() {
+    let system = System::new();
+    system.init_logger();
+
+    let mut tx_id = 0;
+    let initial_amount = 1000000;
+    let token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2);
+    let nft_id: TokenId = (2 << (mem::size_of::<TokenId>() * 8 / 2)) | NFT_BIT;
+    let mtoken = Program::mtoken(&system);
+
+    let create = |is_nft: bool| LogicAction::Create {
+        initial_amount,
+        uri: String::from("https://example.com"),
+        metadata: Default::default(),
+        is_nft,
+        is_soulbound: false,
+        is_edition: false,
+        royalty: None,
+    };
+
+    // The created tokens can be used by the next actions
+    mtoken.multicall(
+        tx_id,
+        USER_ACCOUNTS[0],
+        vec![
+            create(false),
+            LogicAction::MintBatchFT {
+                token_id,
+                to: vec![USER_ACCOUNTS[1].into(), USER_ACCOUNTS[2].into()],
+                amounts: vec![100, 200],
+            },
+            LogicAction::Transfer {
+                token_id,
+                sender: USER_ACCOUNTS[0].into(),
+                recipient: USER_ACCOUNTS[1].into(),
+                amount: 50,
+            },
+            create(true),
+            LogicAction::MintBatchNFT {
+                token_id: nft_id,
+                to: vec![USER_ACCOUNTS[1].into()],
+                metadata: vec![],
+            },
+        ],
+        vec![
+            MTokenEvent::Created(token_id),
+            MTokenEvent::Ok,
+            MTokenEvent::Ok,
+            MTokenEvent::Created(nft_id),
+            MTokenEvent::NFTMinted(vec![nft_id | 1]),
+        ],
+        false,
+    );
+    tx_id += 1;
+
+    assert_eq!(
+        mtoken.get_balance(token_id, USER_ACCOUNTS[0]),
+        initial_amount - 50
+    );
+    assert_eq!(mtoken.get_balance(token_id, USER_ACCOUNTS[1]), 150);
+    assert_eq!(mtoken.get_balance(token_id, USER_ACCOUNTS[2]), 200);
+    assert_eq!(mtoken.get_balance(nft_id | 1, USER_ACCOUNTS[1]), 1);
+
+    // The failed transfer compensates the previous actions
+    let new_token_id: TokenId = 3 << (mem::size_of::<TokenId>() * 8 / 2);
+    mtoken.multicall(
+        tx_id,
+        USER_ACCOUNTS[0],
+        vec![
+            create(false),
+            LogicAction::MintBatchFT {
+                token_id: new_token_id,
+                to: vec![USER_ACCOUNTS[1].into()],
+                amounts: vec![100],
+            },
+            LogicAction::BurnBatchFT {
+                token_id,
+                burn_from: vec![USER_ACCOUNTS[0].into()],
+                amounts: vec![100],
+            },
+            LogicAction::Transfer {
+                token_id,
+                sender: USER_ACCOUNTS[0].into(),
+                recipient: USER_ACCOUNTS[2].into(),
+                amount: initial_amount,
+            },
+        ],
+        vec![],
+        true,
+    );
+    tx_id += 1;
+
+    assert_eq!(mtoken.get_balance(new_token_id, USER_ACCOUNTS[0]), 0);
+    assert_eq!(mtoken.get_balance(new_token_id, USER_ACCOUNTS[1]), 0);
+    assert_eq!(
+        mtoken.get_balance(token_id, USER_ACCOUNTS[0]),
+        initial_amount - 50
+    );
+    assert_eq!(mtoken.get_balance(token_id, USER_ACCOUNTS[2]), 200);
+
+    // The failed transfer restores the previous approval
+    let approve = LogicAction::Approve {
+        account: USER_ACCOUNTS[1].into(),
+        is_approved: true,
+    };
+    mtoken.multicall(
+        tx_id,
+        USER_ACCOUNTS[0],
+        vec![
+            approve.clone(),
+            LogicAction::Transfer {
+                token_id,
+                sender: USER_ACCOUNTS[0].into(),
+                recipient: USER_ACCOUNTS[2].into(),
+                amount: initial_amount,
+            },
+        ],
+        vec![],
+        true,
+    );
+    tx_id += 1;
+
+    assert!(!mtoken.get_approval(USER_ACCOUNTS[0], USER_ACCOUNTS[1]));
+
+    mtoken.multicall(
+        tx_id,
+        USER_ACCOUNTS[0],
+        vec![approve],
+        vec![MTokenEvent::Ok],
+        false,
+    );
+    tx_id += 1;
+
+    assert!(mtoken.get_approval(USER_ACCOUNTS[0], USER_ACCOUNTS[1]));
+
+    // Actions which can't be compensated are rejected
+    mtoken.multicall(
+        tx_id,
+        USER_ACCOUNTS[0],
+        vec![LogicAction::Snapshot { token_id }],
+        vec![],
+        true,
+    );
+    tx_id += 1;
+
+    // The token id of the failed multicall isn't used up
+    mtoken.create(
+        tx_id,
+        USER_ACCOUNTS[0],
+        initial_amount,
+        String::from("https://example.com"),
+        false,
+        false,
+    );
+
+    assert_eq!(
+        mtoken.get_balance(new_token_id, USER_ACCOUNTS[0]),
+        initial_amount
+    );
+}
+
+#[test]
+fn success_storage_retries_executed_once() {
+    let system = System::new();
+    system.init_logger();
+
+    let token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2);
+    let [sender, recipient, spender] = USER_ACCOUNTS;
+    // The account initializing the storage acts as its logic contract
+    let mt_logic = ROOT_ACCOUNT;
+    let storage = Program::from_file(
+        &system,
+        "../target/wasm32-unknown-unknown/debug/mt_storage.wasm",
+    );
+    assert!(!storage
+        .send(mt_logic, InitMTStorage::default())
+        .main_failed());
+
+    let send_and_check_ok = |action: MTStorageAction| {
+        let res = storage.send(mt_logic, action);
+        assert!(res.contains(&(mt_logic, MTStorageEvent::Ok.encode())));
+    };
+    let get_balance = |account: u64| -> u128 {
+        let res = storage.send(
+            mt_logic,
+            MTStorageAction::GetBalance {
+                token_id,
+                account: account.into(),
+            },
+        );
+        res.log()
+            .iter()
+            .find_map(|log| match MTStorageEvent::decode(&mut log.payload()) {
+                Ok(MTStorageEvent::Balance(balance)) => Some(balance),
+                _ => None,
+            })
+            .expect("Unable to get the balance.")
+    };
+    let approve = |transaction_id: u64, approve: bool| MTStorageAction::Approve {
+        transaction_hash: H256::from_low_u64_be(transaction_id),
+        msg_source: sender.into(),
+        account: spender.into(),
+        approve,
+    };
+
+    send_and_check_ok(MTStorageAction::IncreaseBalance {
+        transaction_hash: H256::from_low_u64_be(0),
+        token_id,
+        snapshot_id: 0,
+        account: sender.into(),
+        amount: 1000,
+    });
+
+    // The retried transfer is replied with its status, but tokens move once
+    let transfer = MTStorageAction::Transfer {
+        transaction_hash: H256::from_low_u64_be(1),
+        token_id,
+        snapshot_id: 0,
+        msg_source: sender.into(),
+        sender: sender.into(),
+        recipient: recipient.into(),
+        amount: 100,
+    };
+    send_and_check_ok(transfer.clone());
+    send_and_check_ok(transfer);
+
+    assert_eq!(get_balance(sender), 900);
+    assert_eq!(get_balance(recipient), 100);
+
+    // The retried approval doesn't override the later one
+    send_and_check_ok(approve(2, true));
+    send_and_check_ok(approve(3, false));
+    send_and_check_ok(approve(2, true));
+
+    let res = storage.send(
+        mt_logic,
+        MTStorageAction::GetApproval {
+            account: sender.into(),
+            approval_target: spender.into(),
+        },
+    );
+    assert!(res.contains(&(mt_logic, MTStorageEvent::Approval(false).encode())));
+}
@@ -1,8 +1,9 @@
 mod utils;
 
-use gstd::prelude::*;
+use gstd::{prelude::*, ActorId};
 use gtest::{Program, System};
-use mt_logic_io::{TokenId, NFT_BIT};
+use mt_logic_io::{MTLogicState, NFTItemMetadata, TokenId, NFT_BIT};
+use mt_main_io::{MTokenState, Royalty};
 use std::mem;
 use utils::{MToken, USER_ACCOUNTS};
 
@@ -30,13 +31,14 @@ fn success_create_and_mint_batch_nft() {
     );
     tx_id += 1;
 
-    mtoken.mint_batch_nft(
+    let minted_ids = mtoken.mint_batch_nft(
         tx_id,
         USER_ACCOUNTS[0],
         token_id,
         vec![USER_ACCOUNTS[1], USER_ACCOUNTS[2]],
         false,
     );
+    assert_eq!(minted_ids, vec![minted_id_1, minted_id_2]);
     assert_eq!(mtoken.get_balance(minted_id_1, USER_ACCOUNTS[1]), 1);
     assert_eq!(mtoken.get_balance(minted_id_2, USER_ACCOUNTS[2]), 1);
 }
@@ -152,3 +154,597 @@ fn success_burn_nft() {
     );
     assert_eq!(mtoken.get_balance(minted_id_2, USER_ACCOUNTS[2]), 0);
 }
+
+#[test]
+fn success_mint_batch_nft_with_metadata() {
+    let system = System::new();
+    system.init_logger();
+
+    let mut tx_id = 0;
+    // Abstract `collection` id
+    let token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2) | NFT_BIT;
+    let item_metadata = NFTItemMetadata::Uri(String::from("https://example.com/1"));
+
+    let mtoken = Program::mtoken(&system);
+
+    mtoken.create(
+        tx_id,
+        USER_ACCOUNTS[0],
+        0,
+        String::from("https://example.com"),
+        true,
+        false,
+    );
+    tx_id += 1;
+
+    mtoken.mint_batch_nft_with_metadata(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        vec![USER_ACCOUNTS[1], USER_ACCOUNTS[2]],
+        vec![Some(item_metadata.clone())],
+        true,
+    );
+    tx_id += 1;
+
+    let minted_ids = mtoken.mint_batch_nft_with_metadata(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        vec![USER_ACCOUNTS[1], USER_ACCOUNTS[2]],
+        vec![Some(item_metadata.clone()), None],
+        false,
+    );
+    assert_eq!(minted_ids, vec![token_id | 1, token_id | 2]);
+
+    let state: MTokenState = mtoken.read_state().expect("Unable to read `MTokenState`.");
+    let mt_logic_id: [u8; 32] = state.mt_logic_id.into();
+    let state: MTLogicState = system
+        .get_program(mt_logic_id)
+        .read_state()
+        .expect("Unable to read `MTLogicState`.");
+    assert_eq!(state.nft_metadata, vec![(token_id | 1, item_metadata)]);
+}
+
+#[test]
+fn success_burn_batch_nft() {
+    let system = System::new();
+    system.init_logger();
+
+    let mut tx_id = 0;
+    // Abstract `collection` id
+    let token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2) | NFT_BIT;
+
+    let mtoken = Program::mtoken(&system);
+    let logic_state = || -> MTLogicState {
+        let state: MTokenState = mtoken.read_state().expect("Unable to read `MTokenState`.");
+        let mt_logic_id: [u8; 32] = state.mt_logic_id.into();
+        system
+            .get_program(mt_logic_id)
+            .read_state()
+            .expect("Unable to read `MTLogicState`.")
+    };
+    let collection_supply = || {
+        logic_state()
+            .token_total_supply
+            .into_iter()
+            .find_map(|(id, total_supply)| (id == token_id).then_some(total_supply))
+    };
+
+    mtoken.create(
+        tx_id,
+        USER_ACCOUNTS[0],
+        0,
+        String::from("https://example.com"),
+        true,
+        false,
+    );
+    tx_id += 1;
+
+    let ids = mtoken.mint_batch_nft_with_metadata(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        vec![USER_ACCOUNTS[1], USER_ACCOUNTS[1], USER_ACCOUNTS[2]],
+        vec![
+            Some(NFTItemMetadata::Uri(String::from("https://example.com/1"))),
+            None,
+            None,
+        ],
+        false,
+    );
+    tx_id += 1;
+    assert_eq!(collection_supply(), Some(3));
+
+    let item_royalty = Royalty {
+        receiver: USER_ACCOUNTS[2].into(),
+        basis_points: 1000,
+    };
+    mtoken.set_item_royalty(tx_id, USER_ACCOUNTS[0], ids[0], Some(item_royalty), false);
+    tx_id += 1;
+
+    mtoken.approve(tx_id, USER_ACCOUNTS[2], USER_ACCOUNTS[1], true, false);
+    tx_id += 1;
+
+    // Duplicated ids
+    mtoken.burn_batch_nft(
+        tx_id,
+        USER_ACCOUNTS[1],
+        vec![ids[0], ids[0]],
+        vec![USER_ACCOUNTS[1], USER_ACCOUNTS[1]],
+        true,
+    );
+    tx_id += 1;
+
+    // Invalid owner of the last item
+    mtoken.burn_batch_nft(
+        tx_id,
+        USER_ACCOUNTS[1],
+        vec![ids[0], ids[2]],
+        vec![USER_ACCOUNTS[1], USER_ACCOUNTS[1]],
+        true,
+    );
+    tx_id += 1;
+
+    // Not approved
+    mtoken.burn_batch_nft(
+        tx_id,
+        USER_ACCOUNTS[0],
+        vec![ids[0]],
+        vec![USER_ACCOUNTS[1]],
+        true,
+    );
+    tx_id += 1;
+
+    assert_eq!(mtoken.get_balance(ids[0], USER_ACCOUNTS[1]), 1);
+    assert_eq!(mtoken.get_balance(ids[2], USER_ACCOUNTS[2]), 1);
+    assert_eq!(collection_supply(), Some(3));
+
+    mtoken.burn_batch_nft(
+        tx_id,
+        USER_ACCOUNTS[1],
+        vec![ids[0], ids[2]],
+        vec![USER_ACCOUNTS[1], USER_ACCOUNTS[2]],
+        false,
+    );
+    tx_id += 1;
+
+    assert_eq!(mtoken.get_balance(ids[0], USER_ACCOUNTS[1]), 0);
+    assert_eq!(mtoken.get_balance(ids[1], USER_ACCOUNTS[1]), 1);
+    assert_eq!(mtoken.get_balance(ids[2], USER_ACCOUNTS[2]), 0);
+    assert_eq!(collection_supply(), Some(1));
+
+    // Burned items leave neither metadata nor royalty behind
+    let state = logic_state();
+    assert!(state.nft_metadata.iter().all(|(id, _)| *id != ids[0]));
+    assert!(state.token_royalties.iter().all(|(id, _)| *id != ids[0]));
+
+    let new_ids = mtoken.mint_batch_nft(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        vec![USER_ACCOUNTS[1], USER_ACCOUNTS[1]],
+        false,
+    );
+    tx_id += 1;
+    assert_eq!(collection_supply(), Some(3));
+
+    mtoken.burn_all_nft(tx_id, USER_ACCOUNTS[0], USER_ACCOUNTS[1], true);
+    tx_id += 1;
+    mtoken.burn_all_nft(tx_id, USER_ACCOUNTS[1], USER_ACCOUNTS[1], false);
+
+    assert_eq!(mtoken.get_balance(ids[1], USER_ACCOUNTS[1]), 0);
+    assert_eq!(mtoken.get_balance(new_ids[0], USER_ACCOUNTS[1]), 0);
+    assert_eq!(mtoken.get_balance(new_ids[1], USER_ACCOUNTS[1]), 0);
+    assert_eq!(collection_supply(), Some(0));
+}
+
+#[test]
+fn fail_mint_and_transfer_unknown_nft() {
+    let system = System::new();
+    system.init_logger();
+
+    let mut tx_id = 0;
+    // Abstract `collection` id
+    let token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2) | NFT_BIT;
+    let unknown_token_id: TokenId = 2 << (mem::size_of::<TokenId>() * 8 / 2) | NFT_BIT;
+
+    let mtoken = Program::mtoken(&system);
+
+    mtoken.mint_batch_nft(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        vec![USER_ACCOUNTS[1]],
+        true,
+    );
+    tx_id += 1;
+
+    mtoken.create(
+        tx_id,
+        USER_ACCOUNTS[0],
+        0,
+        String::from("https://example.com"),
+        true,
+        false,
+    );
+    tx_id += 1;
+
+    mtoken.mint_batch_nft(
+        tx_id,
+        USER_ACCOUNTS[0],
+        unknown_token_id,
+        vec![USER_ACCOUNTS[1]],
+        true,
+    );
+    tx_id += 1;
+
+    mtoken.mint_batch_nft(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id | 1,
+        vec![USER_ACCOUNTS[1]],
+        true,
+    );
+    tx_id += 1;
+
+    mtoken.mint_batch_nft(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        vec![USER_ACCOUNTS[1]],
+        false,
+    );
+    tx_id += 1;
+
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[1],
+        token_id | 2,
+        USER_ACCOUNTS[2],
+        0,
+        true,
+    );
+    tx_id += 1;
+
+    mtoken.burn_nft(
+        tx_id,
+        USER_ACCOUNTS[1],
+        token_id | 2,
+        USER_ACCOUNTS[1],
+        true,
+    );
+    tx_id += 1;
+
+    mtoken.burn_nft(tx_id, USER_ACCOUNTS[1], token_id, USER_ACCOUNTS[1], true);
+    tx_id += 1;
+
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[1],
+        token_id | 1,
+        USER_ACCOUNTS[2],
+        0,
+        false,
+    );
+    assert_eq!(mtoken.get_balance(token_id | 1, USER_ACCOUNTS[2]), 1);
+}
+
+#[test]
+fn fail_transfer_soulbound_nft() {
+    let system = System::new();
+    system.init_logger();
+
+    let mut tx_id = 0;
+    // Abstract `collection` id
+    let token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2) | NFT_BIT;
+    let minted_id: TokenId = token_id | 1;
+
+    let mtoken = Program::mtoken(&system);
+
+    mtoken.create_soulbound(tx_id, USER_ACCOUNTS[0], 0, true, false);
+    tx_id += 1;
+
+    mtoken.mint_batch_nft(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        vec![USER_ACCOUNTS[1]],
+        false,
+    );
+    tx_id += 1;
+
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[1],
+        minted_id,
+        USER_ACCOUNTS[2],
+        1,
+        true,
+    );
+    tx_id += 1;
+    assert_eq!(mtoken.get_balance(minted_id, USER_ACCOUNTS[1]), 1);
+
+    // Soulbound tokens still can be burned
+    mtoken.burn_nft(tx_id, USER_ACCOUNTS[1], minted_id, USER_ACCOUNTS[1], false);
+    assert_eq!(mtoken.get_balance(minted_id, USER_ACCOUNTS[1]), 0);
+}
+
+#[test]
+fn success_royalty_info() {
+    let system = System::new();
+    system.init_logger();
+
+    let mut tx_id = 0;
+    // Abstract `collection` id
+    let token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2) | NFT_BIT;
+    let royalty = Royalty {
+        receiver: USER_ACCOUNTS[0].into(),
+        basis_points: 250,
+    };
+    let item_royalty = Royalty {
+        receiver: USER_ACCOUNTS[2].into(),
+        basis_points: 1000,
+    };
+
+    let mtoken = Program::mtoken(&system);
+
+    assert_eq!(mtoken.royalty_info(token_id, 10000), None);
+
+    mtoken.create_with_royalty(
+        tx_id,
+        USER_ACCOUNTS[0],
+        Some(Royalty {
+            basis_points: 10001,
+            ..royalty
+        }),
+        true,
+        true,
+    );
+    tx_id += 1;
+
+    mtoken.create_with_royalty(tx_id, USER_ACCOUNTS[0], Some(royalty), true, false);
+    tx_id += 1;
+
+    let ids = mtoken.mint_batch_nft(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        vec![USER_ACCOUNTS[1], USER_ACCOUNTS[1]],
+        false,
+    );
+    tx_id += 1;
+
+    assert_eq!(
+        mtoken.royalty_info(ids[0], 10000),
+        Some((royalty.receiver, 250))
+    );
+
+    // Only the collection creator can override item royalty
+    mtoken.set_item_royalty(tx_id, USER_ACCOUNTS[1], ids[1], Some(item_royalty), true);
+    tx_id += 1;
+    mtoken.set_item_royalty(tx_id, USER_ACCOUNTS[0], ids[1], Some(item_royalty), false);
+    tx_id += 1;
+
+    assert_eq!(
+        mtoken.royalty_info(ids[0], 10000),
+        Some((royalty.receiver, 250))
+    );
+    assert_eq!(
+        mtoken.royalty_info(ids[1], 10000),
+        Some((item_royalty.receiver, 1000))
+    );
+    assert_eq!(mtoken.royalty_info(token_id | 3, 10000), None);
+
+    mtoken.set_item_royalty(tx_id, USER_ACCOUNTS[0], ids[1], None, false);
+    tx_id += 1;
+    assert_eq!(
+        mtoken.royalty_info(ids[1], 10000),
+        Some((royalty.receiver, 250))
+    );
+
+    // Tokens without royalty pay nothing
+    mtoken.create_with_royalty(tx_id, USER_ACCOUNTS[0], None, true, false);
+    assert_eq!(
+        mtoken.royalty_info(token_id + (1 << (mem::size_of::<TokenId>() * 8 / 2)), 10000),
+        Some((ActorId::zero(), 0))
+    );
+}
+
+#[test]
+fn success_fractionalize_and_redeem() {
+    let system = System::new();
+    system.init_logger();
+
+    let mut tx_id = 0;
+    // Abstract `collection` id
+    let token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2) | NFT_BIT;
+    let fractional_token_id: TokenId = 2 << (mem::size_of::<TokenId>() * 8 / 2);
+
+    let mtoken = Program::mtoken(&system);
+
+    mtoken.create(
+        tx_id,
+        USER_ACCOUNTS[0],
+        0,
+        String::from("https://example.com"),
+        true,
+        false,
+    );
+    tx_id += 1;
+
+    let ids = mtoken.mint_batch_nft(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        vec![USER_ACCOUNTS[0]],
+        false,
+    );
+    tx_id += 1;
+    let nft_id = ids[0];
+
+    // Only the owner can fractionalize the item
+    mtoken.fractionalize(tx_id, USER_ACCOUNTS[1], nft_id, 100, 0, true);
+    tx_id += 1;
+    mtoken.fractionalize(
+        tx_id,
+        USER_ACCOUNTS[0],
+        nft_id,
+        100,
+        fractional_token_id,
+        false,
+    );
+    tx_id += 1;
+
+    // The item is locked until the whole supply is redeemed
+    assert_eq!(mtoken.get_balance(nft_id, USER_ACCOUNTS[0]), 0);
+    assert_eq!(
+        mtoken.get_balance(fractional_token_id, USER_ACCOUNTS[0]),
+        100
+    );
+    mtoken.transfer(tx_id, USER_ACCOUNTS[0], nft_id, USER_ACCOUNTS[1], 1, true);
+    tx_id += 1;
+
+    // Fractions can't be minted anymore
+    mtoken.mint_batch_ft(
+        tx_id,
+        USER_ACCOUNTS[0],
+        fractional_token_id,
+        vec![USER_ACCOUNTS[0]],
+        vec![1],
+        true,
+    );
+    tx_id += 1;
+
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[0],
+        fractional_token_id,
+        USER_ACCOUNTS[1],
+        30,
+        false,
+    );
+    tx_id += 1;
+    mtoken.redeem(tx_id, USER_ACCOUNTS[0], fractional_token_id, true);
+    tx_id += 1;
+    mtoken.transfer(
+        tx_id,
+        USER_ACCOUNTS[1],
+        fractional_token_id,
+        USER_ACCOUNTS[0],
+        30,
+        false,
+    );
+    tx_id += 1;
+    mtoken.redeem(tx_id, USER_ACCOUNTS[0], fractional_token_id, false);
+    tx_id += 1;
+
+    assert_eq!(mtoken.get_balance(nft_id, USER_ACCOUNTS[0]), 1);
+    assert_eq!(mtoken.get_balance(fractional_token_id, USER_ACCOUNTS[0]), 0);
+
+    // The item can't be redeemed twice
+    mtoken.redeem(tx_id, USER_ACCOUNTS[0], fractional_token_id, true);
+}
+
+#[test]
+fn success_edition_nft() {
+    let system = System::new();
+    system.init_logger();
+
+    let mut tx_id = 0;
+    // Abstract `collection` id
+    let token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2) | NFT_BIT;
+
+    let mtoken = Program::mtoken(&system);
+    let total_supply = |token_id: TokenId| {
+        let state: MTokenState = mtoken.read_state().expect("Unable to read `MTokenState`.");
+        let mt_logic_id: [u8; 32] = state.mt_logic_id.into();
+        let logic_state: MTLogicState = system
+            .get_program(mt_logic_id)
+            .read_state()
+            .expect("Unable to read `MTLogicState`.");
+
+        logic_state
+            .token_total_supply
+            .into_iter()
+            .find_map(|(id, total_supply)| (id == token_id).then_some(total_supply))
+    };
+
+    mtoken.create_edition(tx_id, USER_ACCOUNTS[0], false);
+    tx_id += 1;
+
+    // Edition items are minted only by the creator with quantities
+    mtoken.mint_batch_nft(
+        tx_id,
+        USER_ACCOUNTS[0],
+        token_id,
+        vec![USER_ACCOUNTS[0]],
+        true,
+    );
+    tx_id += 1;
+    mtoken.mint_edition(
+        tx_id,
+        USER_ACCOUNTS[1],
+        token_id,
+        vec![USER_ACCOUNTS[1]],
+        vec![10],
+        true,
+    );
+    tx_id += 1;
+
+    let card_id = mtoken
+        .mint_edition(
+            tx_id,
+            USER_ACCOUNTS[0],
+            token_id,
+            vec![USER_ACCOUNTS[0], USER_ACCOUNTS[1]],
+            vec![10, 5],
+            false,
+        )
+        .expect("Edition item must be minted.");
+    tx_id += 1;
+
+    assert_eq!(card_id, token_id | 1);
+    assert_eq!(mtoken.get_balance(card_id, USER_ACCOUNTS[0]), 10);
+    assert_eq!(mtoken.get_balance(card_id, USER_ACCOUNTS[1]), 5);
+
+    // More copies of the existing item are printed
+    assert_eq!(
+        mtoken.mint_edition(
+            tx_id,
+            USER_ACCOUNTS[0],
+            card_id,
+            vec![USER_ACCOUNTS[2]],
+            vec![1],
+            false
+        ),
+        Some(card_id)
+    );
+    tx_id += 1;
+
+    mtoken.transfer(tx_id, USER_ACCOUNTS[0], card_id, USER_ACCOUNTS[2], 3, false);
+    tx_id += 1;
+    mtoken.transfer(tx_id, USER_ACCOUNTS[1], card_id, USER_ACCOUNTS[2], 6, true);
+    tx_id += 1;
+
+    assert_eq!(mtoken.get_balance(card_id, USER_ACCOUNTS[0]), 7);
+    assert_eq!(mtoken.get_balance(card_id, USER_ACCOUNTS[2]), 4);
+
+    // Edition items are burned like fungible tokens
+    mtoken.burn_nft(tx_id, USER_ACCOUNTS[0], card_id, USER_ACCOUNTS[0], true);
+    tx_id += 1;
+    mtoken.burn_batch_ft(
+        tx_id,
+        USER_ACCOUNTS[0],
+        card_id,
+        vec![USER_ACCOUNTS[0]],
+        vec![7],
+        false,
+    );
+
+    assert_eq!(mtoken.get_balance(card_id, USER_ACCOUNTS[0]), 0);
+
+    // Copies of all items are counted in the collection supply
+    assert_eq!(total_supply(card_id), Some(9));
+    assert_eq!(total_supply(token_id), Some(9));
+}
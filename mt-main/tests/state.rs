@@ -0,0 +1,268 @@
+mod utils;
+
+use gstd::{prelude::*, ActorId};
+use gtest::{Program, System};
+use mt_logic_io::{MTLogicState, TokenId, TokenInfo, NFT_BIT};
+use mt_main_io::{MTokenState, TransactionStatus};
+use primitive_types::H256;
+use std::{fs, mem};
+use utils::{MToken, ROOT_ACCOUNT, USER_ACCOUNTS};
+
+const MAIN_STATE_WASM: &str = "../target/wasm32-unknown-unknown/debug/mt_main_state.meta.wasm";
+const LOGIC_STATE_WASM: &str = "../target/wasm32-unknown-unknown/debug/mt_logic_state.meta.wasm";
+const STORAGE_STATE_WASM: &str =
+    "../target/wasm32-unknown-unknown/debug/mt_storage_state.meta.wasm";
+
+fn state_wasm(path: &str) -> Vec<u8> {
+    fs::read(path).expect("Unable to read the state WASM.")
+}
+
+fn transaction_hash(account: u64, transaction_id: u64) -> H256 {
+    let account: [u8; 32] = ActorId::from(account).into();
+    sp_core_hashing::blake2_256(&[account.as_slice(), &transaction_id.to_be_bytes()].concat())
+        .into()
+}
+
+fn mt_logic<'a>(system: &'a System, mtoken: &Program) -> Program<'a> {
+    let state: MTokenState = mtoken.read_state().expect("Unable to read `MTokenState`.");
+    let mt_logic_id: [u8; 32] = state.mt_logic_id.into();
+
+    system.get_program(mt_logic_id)
+}
+
+#[test]
+fn main_state() {
+    let system = System::new();
+    system.init_logger();
+
+    let mtoken = Program::mtoken(&system);
+    let wasm = state_wasm(MAIN_STATE_WASM);
+
+    mtoken.create(
+        0,
+        USER_ACCOUNTS[0],
+        1000,
+        String::from("https://example.com"),
+        false,
+        false,
+    );
+
+    let admin: ActorId = mtoken
+        .read_state_using_wasm("admin", wasm.clone(), None::<()>)
+        .expect("Unable to read the admin.");
+    assert_eq!(admin, ROOT_ACCOUNT.into());
+
+    let state: MTokenState = mtoken.read_state().expect("Unable to read `MTokenState`.");
+    let mt_logic_id: ActorId = mtoken
+        .read_state_using_wasm("mt_logic_id", wasm.clone(), None::<()>)
+        .expect("Unable to read the logic contract id.");
+    assert_eq!(mt_logic_id, state.mt_logic_id);
+
+    let status: Option<TransactionStatus> = mtoken
+        .read_state_using_wasm(
+            "transaction_status",
+            wasm.clone(),
+            Some(transaction_hash(USER_ACCOUNTS[0], 0)),
+        )
+        .expect("Unable to read the transaction status.");
+    assert!(matches!(status, Some(TransactionStatus::Success)));
+
+    let status: Option<TransactionStatus> = mtoken
+        .read_state_using_wasm(
+            "transaction_status",
+            wasm.clone(),
+            Some(transaction_hash(USER_ACCOUNTS[0], 1)),
+        )
+        .expect("Unable to read the transaction status.");
+    assert!(status.is_none());
+
+    let relay_nonce: u64 = mtoken
+        .read_state_using_wasm("relay_nonce", wasm, Some(ActorId::from(USER_ACCOUNTS[0])))
+        .expect("Unable to read the relay nonce.");
+    assert_eq!(relay_nonce, 0);
+}
+
+#[test]
+fn logic_state() {
+    let system = System::new();
+    system.init_logger();
+
+    let token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2);
+    let nft_id: TokenId = (2 << (mem::size_of::<TokenId>() * 8 / 2)) | NFT_BIT;
+    let mtoken = Program::mtoken(&system);
+    let wasm = state_wasm(LOGIC_STATE_WASM);
+
+    mtoken.create(
+        0,
+        USER_ACCOUNTS[0],
+        1000,
+        String::from("https://example.com"),
+        false,
+        false,
+    );
+    mtoken.create(
+        1,
+        USER_ACCOUNTS[0],
+        0,
+        String::from("https://example.com"),
+        true,
+        false,
+    );
+    mtoken.mint_batch_nft(2, USER_ACCOUNTS[0], nft_id, vec![USER_ACCOUNTS[1]], false);
+
+    let mt_logic = mt_logic(&system, &mtoken);
+
+    let token_info: Option<TokenInfo> = mt_logic
+        .read_state_using_wasm("token_info", wasm.clone(), Some(token_id))
+        .expect("Unable to read the token info.");
+    let token_info = token_info.expect("Token not found.");
+    assert_eq!(token_info.creator, USER_ACCOUNTS[0].into());
+    assert_eq!(token_info.uri, "https://example.com");
+    assert_eq!(token_info.total_supply, 1000);
+    assert!(!token_info.is_nft);
+
+    let tokens: Vec<TokenId> = mt_logic
+        .read_state_using_wasm(
+            "tokens_by_creator",
+            wasm.clone(),
+            Some(ActorId::from(USER_ACCOUNTS[0])),
+        )
+        .expect("Unable to read tokens of the creator.");
+    assert_eq!(tokens, vec![token_id, nft_id]);
+
+    let creators: Vec<(TokenId, ActorId)> = mt_logic
+        .read_state_using_wasm("token_creators", wasm.clone(), Some((Some(token_id), 1u32)))
+        .expect("Unable to read token creators.");
+    assert_eq!(creators, vec![(nft_id, USER_ACCOUNTS[0].into())]);
+
+    let owner: Option<ActorId> = mt_logic
+        .read_state_using_wasm("nft_owner", wasm.clone(), Some(nft_id | 1))
+        .expect("Unable to read the NFT owner.");
+    assert_eq!(owner, Some(USER_ACCOUNTS[1].into()));
+
+    let nfts: Vec<TokenId> = mt_logic
+        .read_state_using_wasm(
+            "nfts_of",
+            wasm.clone(),
+            Some(ActorId::from(USER_ACCOUNTS[1])),
+        )
+        .expect("Unable to read NFTs of the account.");
+    assert_eq!(nfts, vec![nft_id | 1]);
+
+    let status: Option<mt_logic_io::TransactionStatus> = mt_logic
+        .read_state_using_wasm(
+            "transaction_status",
+            wasm,
+            Some(transaction_hash(USER_ACCOUNTS[0], 0)),
+        )
+        .expect("Unable to read the transaction status.");
+    assert!(matches!(
+        status,
+        Some(mt_logic_io::TransactionStatus::Success)
+    ));
+}
+
+#[test]
+fn storage_state() {
+    let system = System::new();
+    system.init_logger();
+
+    let token_id: TokenId = 1 << (mem::size_of::<TokenId>() * 8 / 2);
+    let mtoken = Program::mtoken(&system);
+    let wasm = state_wasm(STORAGE_STATE_WASM);
+
+    mtoken.create(
+        0,
+        USER_ACCOUNTS[0],
+        1000,
+        String::from("https://example.com"),
+        false,
+        false,
+    );
+    mtoken.transfer(1, USER_ACCOUNTS[0], token_id, USER_ACCOUNTS[1], 100, false);
+    mtoken.approve(2, USER_ACCOUNTS[0], USER_ACCOUNTS[1], true, false);
+
+    let state: MTLogicState = mt_logic(&system, &mtoken)
+        .read_state()
+        .expect("Unable to read `MTLogicState`.");
+    let storages: Vec<Program> = state
+        .id_to_storage
+        .into_iter()
+        .map(|(_, storage_id)| {
+            let storage_id: [u8; 32] = storage_id.into();
+            system.get_program(storage_id)
+        })
+        .collect();
+
+    // Every account is stored in one of the storages, so the sums are its values
+    let balance_of = |account: u64| -> u128 {
+        storages
+            .iter()
+            .map(|storage| {
+                let balance: u128 = storage
+                    .read_state_using_wasm(
+                        "balance_of",
+                        wasm.clone(),
+                        Some((token_id, ActorId::from(account))),
+                    )
+                    .expect("Unable to read the balance.");
+                balance
+            })
+            .sum()
+    };
+    assert_eq!(balance_of(USER_ACCOUNTS[0]), 900);
+    assert_eq!(balance_of(USER_ACCOUNTS[1]), 100);
+
+    let approvals: Vec<ActorId> = storages
+        .iter()
+        .flat_map(|storage| {
+            let approvals: Vec<ActorId> = storage
+                .read_state_using_wasm(
+                    "approvals_of",
+                    wasm.clone(),
+                    Some(ActorId::from(USER_ACCOUNTS[0])),
+                )
+                .expect("Unable to read approvals of the account.");
+            approvals
+        })
+        .collect();
+    assert_eq!(approvals, vec![USER_ACCOUNTS[1].into()]);
+
+    let holders_count: u32 = storages
+        .iter()
+        .map(|storage| {
+            let holders_count: u32 = storage
+                .read_state_using_wasm("holders_count", wasm.clone(), Some(token_id))
+                .expect("Unable to read the holders count.");
+            holders_count
+        })
+        .sum();
+    assert_eq!(holders_count, 2);
+
+    let tokens: Vec<TokenId> = storages
+        .iter()
+        .flat_map(|storage| {
+            let tokens: Vec<TokenId> = storage
+                .read_state_using_wasm(
+                    "tokens_of",
+                    wasm.clone(),
+                    Some(ActorId::from(USER_ACCOUNTS[1])),
+                )
+                .expect("Unable to read tokens of the account.");
+            tokens
+        })
+        .collect();
+    assert_eq!(tokens, vec![token_id]);
+
+    let is_executed = storages.iter().any(|storage| {
+        let status: Option<bool> = storage
+            .read_state_using_wasm(
+                "transaction_status",
+                wasm.clone(),
+                Some(transaction_hash(USER_ACCOUNTS[0], 0)),
+            )
+            .expect("Unable to read the transaction status.");
+        status == Some(true)
+    });
+    assert!(is_executed);
+}
@@ -1,6 +1,10 @@
 use gstd::{prelude::*, ActorId};
 use gtest::{Program, System};
-use mt_main_io::{InitMToken, LogicAction, MTokenAction, MTokenEvent, TokenId};
+use mt_main_io::{
+    permit_data, relay_message_data, InitMToken, LogicAction, MTokenAction, MTokenEvent,
+    NFTItemMetadata, Royalty, SwapLeg, TokenId, TokenMetadata, TransferFee, SIGNING_CONTEXT,
+};
+use schnorrkel::Keypair;
 
 pub const ROOT_ACCOUNT: u64 = 100;
 
@@ -22,6 +26,19 @@ pub trait MToken {
 
     fn approve(&self, tx_id: u64, from: u64, account: u64, is_approved: bool, error: bool);
 
+    #[allow(clippy::too_many_arguments)]
+    fn permit(
+        &self,
+        tx_id: u64,
+        from: u64,
+        owner: &Keypair,
+        account: u64,
+        is_approved: bool,
+        nonce: u64,
+        deadline: u32,
+        error: bool,
+    );
+
     fn create(
         &self,
         tx_id: u64,
@@ -32,6 +49,38 @@ pub trait MToken {
         error: bool,
     );
 
+    #[allow(clippy::too_many_arguments)]
+    fn create_with_metadata(
+        &self,
+        tx_id: u64,
+        from: u64,
+        initial_amount: u128,
+        uri: String,
+        metadata: TokenMetadata,
+        is_nft: bool,
+        error: bool,
+    );
+
+    fn create_soulbound(
+        &self,
+        tx_id: u64,
+        from: u64,
+        initial_amount: u128,
+        is_nft: bool,
+        error: bool,
+    );
+
+    fn create_edition(&self, tx_id: u64, from: u64, error: bool);
+
+    fn create_with_royalty(
+        &self,
+        tx_id: u64,
+        from: u64,
+        royalty: Option<Royalty>,
+        is_nft: bool,
+        error: bool,
+    );
+
     fn mint_batch_ft(
         &self,
         tx_id: u64,
@@ -42,7 +91,47 @@ pub trait MToken {
         error: bool,
     );
 
-    fn mint_batch_nft(&self, tx_id: u64, from: u64, token_id: TokenId, to: Vec<u64>, error: bool);
+    #[allow(clippy::too_many_arguments)]
+    fn mint_vested(
+        &self,
+        tx_id: u64,
+        from: u64,
+        token_id: TokenId,
+        to: u64,
+        amount: u128,
+        cliff: u32,
+        duration: u32,
+        error: bool,
+    );
+
+    fn mint_batch_nft(
+        &self,
+        tx_id: u64,
+        from: u64,
+        token_id: TokenId,
+        to: Vec<u64>,
+        error: bool,
+    ) -> Vec<TokenId>;
+
+    fn mint_batch_nft_with_metadata(
+        &self,
+        tx_id: u64,
+        from: u64,
+        token_id: TokenId,
+        to: Vec<u64>,
+        metadata: Vec<Option<NFTItemMetadata>>,
+        error: bool,
+    ) -> Vec<TokenId>;
+
+    fn mint_edition(
+        &self,
+        tx_id: u64,
+        from: u64,
+        token_id: TokenId,
+        to: Vec<u64>,
+        amounts: Vec<u128>,
+        error: bool,
+    ) -> Option<TokenId>;
 
     fn burn_batch_ft(
         &self,
@@ -56,11 +145,153 @@ pub trait MToken {
 
     fn burn_nft(&self, tx_id: u64, from: u64, token_id: TokenId, burn_from: u64, error: bool);
 
+    fn burn_batch_nft(
+        &self,
+        tx_id: u64,
+        from: u64,
+        ids: Vec<TokenId>,
+        burn_from: Vec<u64>,
+        error: bool,
+    );
+
+    fn burn_all_nft(&self, tx_id: u64, from: u64, burn_from: u64, error: bool);
+
+    fn update_token_uri(&self, tx_id: u64, from: u64, token_id: TokenId, uri: String, error: bool);
+
+    fn freeze_metadata(&self, tx_id: u64, from: u64, token_id: TokenId, error: bool);
+
+    fn set_item_royalty(
+        &self,
+        tx_id: u64,
+        from: u64,
+        token_id: TokenId,
+        royalty: Option<Royalty>,
+        error: bool,
+    );
+
+    fn set_transfer_fee(
+        &self,
+        tx_id: u64,
+        from: u64,
+        token_id: TokenId,
+        fee: Option<TransferFee>,
+        error: bool,
+    );
+
+    fn freeze_token(&self, tx_id: u64, from: u64, token_id: TokenId, is_frozen: bool, error: bool);
+
+    fn freeze_account(
+        &self,
+        tx_id: u64,
+        from: u64,
+        token_id: TokenId,
+        account: u64,
+        is_frozen: bool,
+        error: bool,
+    );
+
+    fn set_allowlist_mode(
+        &self,
+        tx_id: u64,
+        from: u64,
+        token_id: TokenId,
+        is_enabled: bool,
+        error: bool,
+    );
+
+    fn update_allowlist(
+        &self,
+        tx_id: u64,
+        from: u64,
+        token_id: TokenId,
+        accounts: Vec<u64>,
+        is_allowed: bool,
+        error: bool,
+    );
+
+    fn snapshot(&self, tx_id: u64, from: u64, token_id: TokenId, snapshot_id: u64, error: bool);
+
+    fn propose_swap(
+        &self,
+        tx_id: u64,
+        from: u64,
+        legs: Vec<SwapLeg>,
+        deadline: u32,
+        swap_id: u64,
+        error: bool,
+    );
+
+    fn confirm_swap(&self, tx_id: u64, from: u64, swap_id: u64, error: bool);
+
+    fn cancel_swap(&self, tx_id: u64, from: u64, swap_id: u64, error: bool);
+
+    #[allow(clippy::too_many_arguments)]
+    fn schedule_transfer(
+        &self,
+        tx_id: u64,
+        from: u64,
+        token_id: TokenId,
+        to: u64,
+        amount: u128,
+        at_block: u32,
+        transfer_id: u64,
+        error: bool,
+    );
+
+    fn cancel_scheduled_transfer(&self, tx_id: u64, from: u64, transfer_id: u64, error: bool);
+
+    fn fractionalize(
+        &self,
+        tx_id: u64,
+        from: u64,
+        token_id: TokenId,
+        supply: u128,
+        fractional_token_id: TokenId,
+        error: bool,
+    );
+
+    fn redeem(&self, tx_id: u64, from: u64, token_id: TokenId, error: bool);
+
+    fn multicall(
+        &self,
+        tx_id: u64,
+        from: u64,
+        actions: Vec<LogicAction>,
+        replies: Vec<MTokenEvent>,
+        error: bool,
+    );
+
+    fn deposit(&self, tx_id: u64, from: u64, value: u128, error: bool);
+
+    fn withdraw(&self, tx_id: u64, from: u64, amount: u128, error: bool);
+
+    fn relay_message(
+        &self,
+        from: u64,
+        signer: &Keypair,
+        transaction_id: u64,
+        payload: LogicAction,
+        deadline: u32,
+        error: bool,
+    );
+
     fn send_message_and_check_res(&self, from: u64, payload: MTokenAction, error: bool);
 
+    fn send_message_and_check_reply(&self, from: u64, payload: MTokenAction, reply: MTokenEvent);
+
     fn get_balance(&self, token_id: TokenId, account: u64) -> u128;
 
+    fn balance_of_at(&self, token_id: TokenId, account: u64, snapshot_id: u64) -> Option<u128>;
+
     fn get_approval(&self, account: u64, approval_target: u64) -> bool;
+
+    fn get_token_metadata(&self, token_id: TokenId) -> Option<(TokenMetadata, String, bool)>;
+
+    fn royalty_info(&self, token_id: TokenId, sale_price: u128) -> Option<(ActorId, u128)>;
+
+    fn tokens_of(&self, account: u64, offset: u32, limit: u32) -> Vec<TokenId>;
+    fn holders_of(&self, token_id: TokenId, offset: u32, limit: u32)
+        -> (Vec<(ActorId, u128)>, u32);
 }
 
 impl MToken for Program<'_> {
@@ -128,6 +359,45 @@ impl MToken for Program<'_> {
         );
     }
 
+    fn permit(
+        &self,
+        tx_id: u64,
+        from: u64,
+        owner: &Keypair,
+        account: u64,
+        is_approved: bool,
+        nonce: u64,
+        deadline: u32,
+        error: bool,
+    ) {
+        let mtoken_id = ActorId::from_slice(self.id().as_ref()).expect("Invalid program id.");
+        let owner_id = ActorId::new(owner.public.to_bytes());
+        let data = permit_data(
+            &mtoken_id,
+            &owner_id,
+            &account.into(),
+            is_approved,
+            nonce,
+            deadline,
+        );
+        let payload = LogicAction::Permit {
+            owner: owner_id,
+            account: account.into(),
+            is_approved,
+            deadline,
+            signature: owner.sign_simple(SIGNING_CONTEXT, &data).to_bytes(),
+        };
+
+        self.send_message_and_check_res(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+            error,
+        );
+    }
+
     fn create(
         &self,
         tx_id: u64,
@@ -136,11 +406,36 @@ impl MToken for Program<'_> {
         uri: String,
         is_nft: bool,
         error: bool,
+    ) {
+        self.create_with_metadata(
+            tx_id,
+            from,
+            initial_amount,
+            uri,
+            Default::default(),
+            is_nft,
+            error,
+        );
+    }
+
+    fn create_with_metadata(
+        &self,
+        tx_id: u64,
+        from: u64,
+        initial_amount: u128,
+        uri: String,
+        metadata: TokenMetadata,
+        is_nft: bool,
+        error: bool,
     ) {
         let payload = LogicAction::Create {
             initial_amount,
             uri,
+            metadata,
             is_nft,
+            is_soulbound: false,
+            is_edition: false,
+            royalty: None,
         };
 
         self.send_message_and_check_res(
@@ -153,19 +448,22 @@ impl MToken for Program<'_> {
         );
     }
 
-    fn mint_batch_ft(
+    fn create_soulbound(
         &self,
         tx_id: u64,
         from: u64,
-        token_id: TokenId,
-        to: Vec<u64>,
-        amounts: Vec<u128>,
+        initial_amount: u128,
+        is_nft: bool,
         error: bool,
     ) {
-        let payload = LogicAction::MintBatchFT {
-            token_id,
-            to: to.iter().map(|id| Into::<ActorId>::into(*id)).collect(),
-            amounts,
+        let payload = LogicAction::Create {
+            initial_amount,
+            uri: String::from("https://example.com"),
+            metadata: Default::default(),
+            is_nft,
+            is_soulbound: true,
+            is_edition: false,
+            royalty: None,
         };
 
         self.send_message_and_check_res(
@@ -178,10 +476,15 @@ impl MToken for Program<'_> {
         );
     }
 
-    fn mint_batch_nft(&self, tx_id: u64, from: u64, token_id: TokenId, to: Vec<u64>, error: bool) {
-        let payload = LogicAction::MintBatchNFT {
-            token_id,
-            to: to.iter().map(|id| Into::<ActorId>::into(*id)).collect(),
+    fn create_edition(&self, tx_id: u64, from: u64, error: bool) {
+        let payload = LogicAction::Create {
+            initial_amount: 0,
+            uri: String::from("https://example.com"),
+            metadata: Default::default(),
+            is_nft: true,
+            is_soulbound: false,
+            is_edition: true,
+            royalty: None,
         };
 
         self.send_message_and_check_res(
@@ -194,21 +497,46 @@ impl MToken for Program<'_> {
         );
     }
 
-    fn burn_batch_ft(
+    fn create_with_royalty(
+        &self,
+        tx_id: u64,
+        from: u64,
+        royalty: Option<Royalty>,
+        is_nft: bool,
+        error: bool,
+    ) {
+        let payload = LogicAction::Create {
+            initial_amount: 0,
+            uri: String::from("https://example.com"),
+            metadata: Default::default(),
+            is_nft,
+            is_soulbound: false,
+            is_edition: false,
+            royalty,
+        };
+
+        self.send_message_and_check_res(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+            error,
+        );
+    }
+
+    fn mint_batch_ft(
         &self,
         tx_id: u64,
         from: u64,
         token_id: TokenId,
-        burn_from: Vec<u64>,
+        to: Vec<u64>,
         amounts: Vec<u128>,
         error: bool,
     ) {
-        let payload = LogicAction::BurnBatchFT {
+        let payload = LogicAction::MintBatchFT {
             token_id,
-            burn_from: burn_from
-                .iter()
-                .map(|id| Into::<ActorId>::into(*id))
-                .collect(),
+            to: to.iter().map(|id| Into::<ActorId>::into(*id)).collect(),
             amounts,
         };
 
@@ -222,10 +550,23 @@ impl MToken for Program<'_> {
         );
     }
 
-    fn burn_nft(&self, tx_id: u64, from: u64, token_id: TokenId, burn_from: u64, error: bool) {
-        let payload = LogicAction::BurnNFT {
+    fn mint_vested(
+        &self,
+        tx_id: u64,
+        from: u64,
+        token_id: TokenId,
+        to: u64,
+        amount: u128,
+        cliff: u32,
+        duration: u32,
+        error: bool,
+    ) {
+        let payload = LogicAction::MintVested {
             token_id,
-            from: burn_from.into(),
+            to: to.into(),
+            amount,
+            cliff,
+            duration,
         };
 
         self.send_message_and_check_res(
@@ -238,16 +579,592 @@ impl MToken for Program<'_> {
         );
     }
 
-    fn send_message_and_check_res(&self, from: u64, payload: MTokenAction, error: bool) {
-        let res = self.send(from, payload);
-        let reply = if error {
-            MTokenEvent::Err.encode()
-        } else {
-            MTokenEvent::Ok.encode()
-        };
-
-        assert!(res.contains(&(from, reply)));
-    }
+    fn mint_batch_nft(
+        &self,
+        tx_id: u64,
+        from: u64,
+        token_id: TokenId,
+        to: Vec<u64>,
+        error: bool,
+    ) -> Vec<TokenId> {
+        self.mint_batch_nft_with_metadata(tx_id, from, token_id, to, vec![], error)
+    }
+
+    fn mint_batch_nft_with_metadata(
+        &self,
+        tx_id: u64,
+        from: u64,
+        token_id: TokenId,
+        to: Vec<u64>,
+        metadata: Vec<Option<NFTItemMetadata>>,
+        error: bool,
+    ) -> Vec<TokenId> {
+        let payload = LogicAction::MintBatchNFT {
+            token_id,
+            to: to.iter().map(|id| Into::<ActorId>::into(*id)).collect(),
+            metadata,
+        };
+
+        let res = self.send(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+        );
+
+        if error {
+            assert!(res.contains(&(from, MTokenEvent::Err.encode())));
+            return vec![];
+        }
+
+        res.log()
+            .iter()
+            .find_map(|log| {
+                if let Ok(MTokenEvent::NFTMinted(ids)) = MTokenEvent::decode(&mut log.payload()) {
+                    Some(ids)
+                } else {
+                    None
+                }
+            })
+            .expect("`MTokenEvent::NFTMinted` not found in reply.")
+    }
+
+    fn mint_edition(
+        &self,
+        tx_id: u64,
+        from: u64,
+        token_id: TokenId,
+        to: Vec<u64>,
+        amounts: Vec<u128>,
+        error: bool,
+    ) -> Option<TokenId> {
+        let payload = LogicAction::MintEdition {
+            token_id,
+            to: to.iter().map(|id| Into::<ActorId>::into(*id)).collect(),
+            amounts,
+        };
+
+        let res = self.send(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+        );
+
+        if error {
+            assert!(res.contains(&(from, MTokenEvent::Err.encode())));
+            return None;
+        }
+
+        res.log().iter().find_map(|log| {
+            if let Ok(MTokenEvent::NFTMinted(ids)) = MTokenEvent::decode(&mut log.payload()) {
+                ids.first().copied()
+            } else {
+                None
+            }
+        })
+    }
+
+    fn burn_batch_ft(
+        &self,
+        tx_id: u64,
+        from: u64,
+        token_id: TokenId,
+        burn_from: Vec<u64>,
+        amounts: Vec<u128>,
+        error: bool,
+    ) {
+        let payload = LogicAction::BurnBatchFT {
+            token_id,
+            burn_from: burn_from
+                .iter()
+                .map(|id| Into::<ActorId>::into(*id))
+                .collect(),
+            amounts,
+        };
+
+        self.send_message_and_check_res(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+            error,
+        );
+    }
+
+    fn burn_nft(&self, tx_id: u64, from: u64, token_id: TokenId, burn_from: u64, error: bool) {
+        let payload = LogicAction::BurnNFT {
+            token_id,
+            from: burn_from.into(),
+        };
+
+        self.send_message_and_check_res(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+            error,
+        );
+    }
+
+    fn burn_batch_nft(
+        &self,
+        tx_id: u64,
+        from: u64,
+        ids: Vec<TokenId>,
+        burn_from: Vec<u64>,
+        error: bool,
+    ) {
+        let payload = LogicAction::BurnBatchNFT {
+            ids,
+            from: burn_from.into_iter().map(Into::into).collect(),
+        };
+
+        self.send_message_and_check_res(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+            error,
+        );
+    }
+
+    fn burn_all_nft(&self, tx_id: u64, from: u64, burn_from: u64, error: bool) {
+        let payload = LogicAction::BurnAllNFT {
+            from: burn_from.into(),
+        };
+
+        self.send_message_and_check_res(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+            error,
+        );
+    }
+
+    fn update_token_uri(&self, tx_id: u64, from: u64, token_id: TokenId, uri: String, error: bool) {
+        let payload = LogicAction::UpdateTokenUri {
+            token_id,
+            uri: uri.clone(),
+        };
+        let reply = if error {
+            MTokenEvent::Err
+        } else {
+            MTokenEvent::TokenUriChanged { token_id, uri }
+        };
+
+        self.send_message_and_check_reply(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+            reply,
+        );
+    }
+
+    fn freeze_metadata(&self, tx_id: u64, from: u64, token_id: TokenId, error: bool) {
+        let payload = LogicAction::FreezeMetadata { token_id };
+
+        self.send_message_and_check_res(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+            error,
+        );
+    }
+
+    fn set_item_royalty(
+        &self,
+        tx_id: u64,
+        from: u64,
+        token_id: TokenId,
+        royalty: Option<Royalty>,
+        error: bool,
+    ) {
+        let payload = LogicAction::SetItemRoyalty { token_id, royalty };
+
+        self.send_message_and_check_res(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+            error,
+        );
+    }
+
+    fn set_transfer_fee(
+        &self,
+        tx_id: u64,
+        from: u64,
+        token_id: TokenId,
+        fee: Option<TransferFee>,
+        error: bool,
+    ) {
+        let payload = LogicAction::SetTransferFee { token_id, fee };
+
+        self.send_message_and_check_res(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+            error,
+        );
+    }
+
+    fn freeze_token(&self, tx_id: u64, from: u64, token_id: TokenId, is_frozen: bool, error: bool) {
+        let payload = LogicAction::FreezeToken {
+            token_id,
+            is_frozen,
+        };
+
+        self.send_message_and_check_res(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+            error,
+        );
+    }
+
+    fn freeze_account(
+        &self,
+        tx_id: u64,
+        from: u64,
+        token_id: TokenId,
+        account: u64,
+        is_frozen: bool,
+        error: bool,
+    ) {
+        let payload = LogicAction::FreezeAccount {
+            token_id,
+            account: account.into(),
+            is_frozen,
+        };
+
+        self.send_message_and_check_res(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+            error,
+        );
+    }
+
+    fn set_allowlist_mode(
+        &self,
+        tx_id: u64,
+        from: u64,
+        token_id: TokenId,
+        is_enabled: bool,
+        error: bool,
+    ) {
+        let payload = LogicAction::SetAllowlistMode {
+            token_id,
+            is_enabled,
+        };
+
+        self.send_message_and_check_res(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+            error,
+        );
+    }
+
+    fn update_allowlist(
+        &self,
+        tx_id: u64,
+        from: u64,
+        token_id: TokenId,
+        accounts: Vec<u64>,
+        is_allowed: bool,
+        error: bool,
+    ) {
+        let payload = LogicAction::UpdateAllowlist {
+            token_id,
+            accounts: accounts.into_iter().map(|account| account.into()).collect(),
+            is_allowed,
+        };
+
+        self.send_message_and_check_res(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+            error,
+        );
+    }
+
+    fn snapshot(&self, tx_id: u64, from: u64, token_id: TokenId, snapshot_id: u64, error: bool) {
+        let payload = LogicAction::Snapshot { token_id };
+        let reply = if error {
+            MTokenEvent::Err
+        } else {
+            MTokenEvent::SnapshotTaken {
+                token_id,
+                snapshot_id,
+            }
+        };
+
+        self.send_message_and_check_reply(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+            reply,
+        );
+    }
+
+    fn propose_swap(
+        &self,
+        tx_id: u64,
+        from: u64,
+        legs: Vec<SwapLeg>,
+        deadline: u32,
+        swap_id: u64,
+        error: bool,
+    ) {
+        let payload = LogicAction::ProposeSwap { legs, deadline };
+        let reply = if error {
+            MTokenEvent::Err
+        } else {
+            MTokenEvent::SwapProposed(swap_id)
+        };
+
+        self.send_message_and_check_reply(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+            reply,
+        );
+    }
+
+    fn confirm_swap(&self, tx_id: u64, from: u64, swap_id: u64, error: bool) {
+        let payload = LogicAction::ConfirmSwap { swap_id };
+
+        self.send_message_and_check_res(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+            error,
+        );
+    }
+
+    fn cancel_swap(&self, tx_id: u64, from: u64, swap_id: u64, error: bool) {
+        let payload = LogicAction::CancelSwap { swap_id };
+
+        self.send_message_and_check_res(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+            error,
+        );
+    }
+
+    fn schedule_transfer(
+        &self,
+        tx_id: u64,
+        from: u64,
+        token_id: TokenId,
+        to: u64,
+        amount: u128,
+        at_block: u32,
+        transfer_id: u64,
+        error: bool,
+    ) {
+        let payload = LogicAction::ScheduleTransfer {
+            token_id,
+            recipient: to.into(),
+            amount,
+            at_block,
+        };
+        let reply = if error {
+            MTokenEvent::Err
+        } else {
+            MTokenEvent::TransferScheduled(transfer_id)
+        };
+
+        self.send_message_and_check_reply(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+            reply,
+        );
+    }
+
+    fn cancel_scheduled_transfer(&self, tx_id: u64, from: u64, transfer_id: u64, error: bool) {
+        let payload = LogicAction::CancelScheduledTransfer { transfer_id };
+
+        self.send_message_and_check_res(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+            error,
+        );
+    }
+
+    fn fractionalize(
+        &self,
+        tx_id: u64,
+        from: u64,
+        token_id: TokenId,
+        supply: u128,
+        fractional_token_id: TokenId,
+        error: bool,
+    ) {
+        let payload = LogicAction::Fractionalize { token_id, supply };
+        let reply = if error {
+            MTokenEvent::Err
+        } else {
+            MTokenEvent::Fractionalized(fractional_token_id)
+        };
+
+        self.send_message_and_check_reply(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+            reply,
+        );
+    }
+
+    fn redeem(&self, tx_id: u64, from: u64, token_id: TokenId, error: bool) {
+        let payload = LogicAction::Redeem { token_id };
+
+        self.send_message_and_check_res(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+            error,
+        );
+    }
+
+    fn multicall(
+        &self,
+        tx_id: u64,
+        from: u64,
+        actions: Vec<LogicAction>,
+        replies: Vec<MTokenEvent>,
+        error: bool,
+    ) {
+        let payload = LogicAction::Multicall(actions);
+        let reply = if error {
+            MTokenEvent::Err
+        } else {
+            MTokenEvent::Multicall(replies)
+        };
+
+        self.send_message_and_check_reply(
+            from,
+            MTokenAction::Message {
+                transaction_id: tx_id,
+                payload,
+            },
+            reply,
+        );
+    }
+
+    fn deposit(&self, tx_id: u64, from: u64, value: u128, error: bool) {
+        let reply = if error {
+            MTokenEvent::Err
+        } else {
+            MTokenEvent::Ok
+        };
+
+        let res = self.send_with_value(
+            from,
+            MTokenAction::Deposit {
+                transaction_id: tx_id,
+            },
+            value,
+        );
+
+        assert!(res.contains(&(from, reply.encode())));
+    }
+
+    fn withdraw(&self, tx_id: u64, from: u64, amount: u128, error: bool) {
+        self.send_message_and_check_res(
+            from,
+            MTokenAction::Withdraw {
+                transaction_id: tx_id,
+                amount,
+            },
+            error,
+        );
+    }
+
+    fn relay_message(
+        &self,
+        from: u64,
+        signer: &Keypair,
+        transaction_id: u64,
+        payload: LogicAction,
+        deadline: u32,
+        error: bool,
+    ) {
+        let mtoken_id = ActorId::from_slice(self.id().as_ref()).expect("Invalid program id.");
+        let signer_id = ActorId::new(signer.public.to_bytes());
+        let data = relay_message_data(&mtoken_id, &signer_id, transaction_id, &payload, deadline);
+
+        self.send_message_and_check_res(
+            from,
+            MTokenAction::RelayMessage {
+                signer: signer_id,
+                transaction_id,
+                payload,
+                signature: signer.sign_simple(SIGNING_CONTEXT, &data).to_bytes(),
+                deadline,
+            },
+            error,
+        );
+    }
+
+    fn send_message_and_check_res(&self, from: u64, payload: MTokenAction, error: bool) {
+        let reply = if error {
+            MTokenEvent::Err
+        } else {
+            MTokenEvent::Ok
+        };
+
+        self.send_message_and_check_reply(from, payload, reply);
+    }
+
+    fn send_message_and_check_reply(&self, from: u64, payload: MTokenAction, reply: MTokenEvent) {
+        let res = self.send(from, payload);
+
+        assert!(res.contains(&(from, reply.encode())));
+    }
 
     fn get_balance(&self, token_id: TokenId, account: u64) -> u128 {
         let res = self.send(
@@ -274,6 +1191,26 @@ impl MToken for Program<'_> {
         balance
     }
 
+    fn balance_of_at(&self, token_id: TokenId, account: u64, snapshot_id: u64) -> Option<u128> {
+        let res = self.send(
+            account,
+            MTokenAction::BalanceOfAt {
+                token_id,
+                account: account.into(),
+                snapshot_id,
+            },
+        );
+        assert!(!res.main_failed());
+
+        res.log().iter().find_map(|log| {
+            if let Ok(MTokenEvent::Balance(balance)) = MTokenEvent::decode(&mut log.payload()) {
+                Some(balance)
+            } else {
+                None
+            }
+        })
+    }
+
     fn get_approval(&self, account: u64, approval_target: u64) -> bool {
         let res = self.send(
             account,
@@ -299,4 +1236,96 @@ impl MToken for Program<'_> {
 
         approval
     }
+
+    fn get_token_metadata(&self, token_id: TokenId) -> Option<(TokenMetadata, String, bool)> {
+        let res = self.send(ROOT_ACCOUNT, MTokenAction::GetTokenMetadata { token_id });
+        assert!(!res.main_failed());
+
+        res.log().iter().find_map(|log| {
+            if let Ok(MTokenEvent::TokenMetadata {
+                metadata,
+                uri,
+                is_frozen,
+            }) = MTokenEvent::decode(&mut log.payload())
+            {
+                Some((metadata, uri, is_frozen))
+            } else {
+                None
+            }
+        })
+    }
+
+    fn royalty_info(&self, token_id: TokenId, sale_price: u128) -> Option<(ActorId, u128)> {
+        let res = self.send(
+            ROOT_ACCOUNT,
+            MTokenAction::RoyaltyInfo {
+                token_id,
+                sale_price,
+            },
+        );
+        assert!(!res.main_failed());
+
+        res.log().iter().find_map(|log| {
+            if let Ok(MTokenEvent::RoyaltyInfo { receiver, amount }) =
+                MTokenEvent::decode(&mut log.payload())
+            {
+                Some((receiver, amount))
+            } else {
+                None
+            }
+        })
+    }
+
+    fn tokens_of(&self, account: u64, offset: u32, limit: u32) -> Vec<TokenId> {
+        let res = self.send(
+            account,
+            MTokenAction::TokensOf {
+                account: account.into(),
+                offset,
+                limit,
+            },
+        );
+        assert!(!res.main_failed());
+
+        res.log()
+            .iter()
+            .find_map(|log| {
+                if let Ok(MTokenEvent::TokensOf(tokens)) = MTokenEvent::decode(&mut log.payload()) {
+                    Some(tokens)
+                } else {
+                    None
+                }
+            })
+            .expect("`MTokenEvent::TokensOf` not found in reply.")
+    }
+
+    fn holders_of(
+        &self,
+        token_id: TokenId,
+        offset: u32,
+        limit: u32,
+    ) -> (Vec<(ActorId, u128)>, u32) {
+        let res = self.send(
+            ROOT_ACCOUNT,
+            MTokenAction::HoldersOf {
+                token_id,
+                offset,
+                limit,
+            },
+        );
+        assert!(!res.main_failed());
+
+        res.log()
+            .iter()
+            .find_map(|log| {
+                if let Ok(MTokenEvent::HoldersOf { holders, total }) =
+                    MTokenEvent::decode(&mut log.payload())
+                {
+                    Some((holders, total))
+                } else {
+                    None
+                }
+            })
+            .expect("`MTokenEvent::HoldersOf` not found in reply.")
+    }
 }
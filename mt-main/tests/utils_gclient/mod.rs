@@ -144,9 +144,11 @@ pub async fn send_mtoken_message(
     let (_, reply_data_result, _) = listener.reply_bytes_on(message_id).await?;
     let reply = reply_data_result.expect("Unexpected invalid reply.");
 
-    let mt_main_io::MTokenEvent::Ok = mt_main_io::MTokenEvent::decode(&mut reply.as_ref()).expect("Unexpected invalid `MTokenEvent` data.") else {
+    if let MTokenEvent::Err =
+        MTokenEvent::decode(&mut reply.as_ref()).expect("Unexpected invalid `MTokenEvent` data.")
+    {
         panic!("Unexpected invalid `MTokenEvent`.");
-    };
+    }
 
     Ok(())
 }
@@ -168,7 +170,11 @@ pub async fn mtoken_create(
         LogicAction::Create {
             initial_amount,
             uri: uri.as_ref().to_owned(),
+            metadata: Default::default(),
             is_nft,
+            is_soulbound: false,
+            is_edition: false,
+            royalty: None,
         },
     )
     .await
@@ -261,7 +267,11 @@ pub async fn mtoken_mint_batch_nft(
         listener,
         program_id,
         tx_id,
-        LogicAction::MintBatchNFT { token_id, to },
+        LogicAction::MintBatchNFT {
+            token_id,
+            to,
+            metadata: vec![],
+        },
     )
     .await
 }
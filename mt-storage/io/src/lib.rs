@@ -1,15 +1,48 @@
 #![no_std]
 
-use gmeta::{InOut, Metadata};
-use gstd::{prelude::*, ActorId};
+use core::ops::Bound;
+use gmeta::{In, InOut, Metadata};
+use gstd::{
+    prelude::{collections::BTreeMap, *},
+    ActorId,
+};
 use primitive_types::H256;
 
 pub type TokenId = u128;
 
+/// Returns at most `limit` entries with keys greater than `after` ordered by key.
+///
+/// The key of the last returned entry is the `after` cursor of the next page.
+pub fn paginate<K: Ord + Clone, V: Clone>(
+    entries: &BTreeMap<K, V>,
+    after: Option<K>,
+    limit: u32,
+) -> Vec<(K, V)> {
+    entries
+        .range((
+            after.map_or(Bound::Unbounded, Bound::Excluded),
+            Bound::Unbounded,
+        ))
+        .take(limit as usize)
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
+/// Same as [`paginate`] for `entries` sorted by key, as they are in contract states.
+pub fn paginate_sorted<K: Ord, V>(
+    mut entries: Vec<(K, V)>,
+    after: Option<K>,
+    limit: u32,
+) -> Vec<(K, V)> {
+    let start = after.map_or(0, |after| entries.partition_point(|(key, _)| *key <= after));
+
+    entries.drain(start..).take(limit as usize).collect()
+}
+
 pub struct MTStorageMetadata;
 
 impl Metadata for MTStorageMetadata {
-    type Init = ();
+    type Init = In<InitMTStorage>;
     type Handle = InOut<MTStorageAction, MTStorageEvent>;
     type Others = ();
     type Reply = ();
@@ -28,6 +61,62 @@ pub struct MTStorageState {
     pub balances: Vec<(TokenId, Vec<(ActorId, u128)>)>,
     /// Mapping with approvals: `ActorId` -> `ActorId` -> `bool`.
     pub approvals: Vec<(ActorId, Vec<(ActorId, bool)>)>,
+    /// Mapping with tokens held by accounts: `ActorId` -> `Vec<TokenId>`.
+    pub account_tokens: Vec<(ActorId, Vec<TokenId>)>,
+    /// Mapping with number of accounts with non-zero balance: `TokenId` -> `u32`.
+    pub holders_count: Vec<(TokenId, u32)>,
+    /// Mapping with the latest snapshot ids: `TokenId` -> `u64`.
+    pub current_snapshots: Vec<(TokenId, u64)>,
+    /// Mapping with balances recorded at snapshots: `TokenId` -> `ActorId` -> `(u64, u128)`.
+    ///
+    /// A balance is recorded on the first change after the snapshot is taken.
+    pub snapshots: Vec<(TokenId, Vec<(ActorId, Vec<(u64, u128)>)>)>,
+    /// Mapping with vesting schedules of locked balances: `TokenId` -> `ActorId` -> `Vec<VestingSchedule>`.
+    pub vestings: Vec<(TokenId, Vec<(ActorId, Vec<VestingSchedule>)>)>,
+}
+
+/// Linear release of `amount` tokens over `duration` blocks since `start`,
+/// nothing is released until `cliff` blocks pass.
+#[derive(Encode, Decode, Clone, Copy, Debug, Default, PartialEq, Eq, TypeInfo)]
+pub struct VestingSchedule {
+    /// Number of vested tokens.
+    pub amount: u128,
+    /// Block height at which the vesting starts.
+    pub start: u32,
+    /// Number of blocks since `start` before the first release.
+    pub cliff: u32,
+    /// Number of blocks since `start` after which all tokens are released.
+    pub duration: u32,
+}
+
+impl VestingSchedule {
+    /// Returns the number of tokens which are still locked at `block_height`.
+    pub fn locked(&self, block_height: u32) -> u128 {
+        let elapsed = block_height.saturating_sub(self.start);
+
+        if elapsed < self.cliff {
+            return self.amount;
+        }
+
+        if elapsed >= self.duration {
+            return 0;
+        }
+
+        let released = self
+            .amount
+            .checked_mul(elapsed as u128)
+            .map(|amount| amount / self.duration as u128)
+            .unwrap_or_else(|| self.amount / self.duration as u128 * elapsed as u128);
+
+        self.amount - released
+    }
+}
+
+/// Initializes the contract.
+#[derive(Encode, Decode, Clone, Debug, Default, TypeInfo)]
+pub struct InitMTStorage {
+    /// The latest snapshot ids of tokens at the moment of the storage creation.
+    pub snapshots: Vec<(TokenId, u64)>,
 }
 
 /// Sends the contract info about what it should do.
@@ -35,8 +124,7 @@ pub struct MTStorageState {
 pub enum MTStorageAction {
     /// Returns `account` token balance.
     ///
-    /// # Requirements
-    /// - `token_id` must exists in [`MTStorageState`] state, in `balances` field.
+    /// If `token_id` or `account` is unknown to this storage, the balance is zero.
     ///
     /// On success, replies with [`MTStorageEvent::Balance`].
     GetBalance {
@@ -47,8 +135,7 @@ pub enum MTStorageAction {
     },
     /// Returns status approval for `approval_target` from `account`.
     ///
-    /// # Requirements
-    /// - `account` must exists in [`MTStorageState`] state, in `approvals` field.
+    /// If `account` has never given any approvals, the status is `false`.
     ///
     /// On success, replies with [`MTStorageEvent::Approval`].
     GetApproval {
@@ -70,6 +157,8 @@ pub enum MTStorageAction {
         transaction_hash: H256,
         /// Identifier of the token with which transfer will be performed.
         token_id: TokenId,
+        /// The latest snapshot id of `token_id` at the moment the operation is created.
+        snapshot_id: u64,
         /// The actual account that made the transfer (initiator).
         msg_source: ActorId,
         /// Account from which tokens will be transferred.
@@ -95,6 +184,69 @@ pub enum MTStorageAction {
         /// Approve flag.
         approve: bool,
     },
+    /// Returns a page of balances ordered by token id and account.
+    ///
+    /// On success, replies with [`MTStorageEvent::Balances`].
+    GetBalances {
+        /// Token id and account of the last entry of the previous page, `None` for the first page.
+        after: Option<(TokenId, ActorId)>,
+        /// Maximum number of entries to return.
+        limit: u32,
+    },
+    /// Returns a page of tokens with non-zero `account` balance ordered by token id.
+    ///
+    /// On success, replies with [`MTStorageEvent::TokensOf`].
+    TokensOf {
+        /// Specifies the account whose tokens you want to find out.
+        account: ActorId,
+        /// Number of entries to skip.
+        offset: u32,
+        /// Maximum number of entries to return.
+        limit: u32,
+    },
+    /// Returns a page of `token_id` holders with their balances ordered by account.
+    ///
+    /// On success, replies with [`MTStorageEvent::HoldersOf`].
+    HoldersOf {
+        /// Token ID to get the holders.
+        token_id: TokenId,
+        /// Number of entries to skip.
+        offset: u32,
+        /// Maximum number of entries to return.
+        limit: u32,
+    },
+    /// Returns `account` balance of `token_id` tokens at the moment
+    /// when `snapshot_id` was taken.
+    ///
+    /// # Requirements
+    /// - `snapshot_id` must be taken for `token_id`.
+    ///
+    /// On success, replies with [`MTStorageEvent::Balance`].
+    BalanceOfAt {
+        /// Token ID to get the balance.
+        token_id: TokenId,
+        /// Specifies the account whose balance you want to find out.
+        account: ActorId,
+        /// Snapshot ID to get the balance at.
+        snapshot_id: u64,
+    },
+    /// Starts recording balances of `token_id` tokens for `snapshot_id`.
+    ///
+    /// Balance changes carry the latest snapshot id as well, so a storage switches
+    /// to the snapshot with the first of them, even if this message arrives later.
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be multitoken logic contract.
+    ///
+    /// On success, replies with [`MTStorageEvent::Ok`].
+    Snapshot {
+        /// Unique snapshot transaction hash.
+        transaction_hash: H256,
+        /// Token ID to take the snapshot.
+        token_id: TokenId,
+        /// Snapshot ID assigned by multitoken logic contract.
+        snapshot_id: u64,
+    },
     /// Deletes the stored transaction entity with its status by unique hash.
     ClearTransaction(H256),
     /// Increase `account` balance of `token_id` tokens.
@@ -108,15 +260,37 @@ pub enum MTStorageAction {
         transaction_hash: H256,
         /// Identifier of the token with which increase balance will be performed.
         token_id: TokenId,
+        /// The latest snapshot id of `token_id` at the moment the operation is created.
+        snapshot_id: u64,
         /// An account that needs to increase its balance.
         account: ActorId,
         /// Number of tokens by which the balance will be increased.
         amount: u128,
     },
+    /// Increase `account` balance of `token_id` tokens, which are locked
+    /// until they are released by `schedule`.
+    ///
+    /// # Requirements
+    /// - [`msg::source()`](gstd::msg::source) must be multitoken logic contract.
+    ///
+    /// On success, replies with [`MTStorageEvent::Ok`].
+    IncreaseVestedBalance {
+        /// Unique operation transaction hash.
+        transaction_hash: H256,
+        /// Identifier of the token with which increase balance will be performed.
+        token_id: TokenId,
+        /// The latest snapshot id of `token_id` at the moment the operation is created.
+        snapshot_id: u64,
+        /// An account that needs to increase its balance.
+        account: ActorId,
+        /// Number of tokens and their release schedule.
+        schedule: VestingSchedule,
+    },
     /// Decrease `account` balance of `token_id` tokens.
     ///
     /// # Requirements
     /// - [`msg::source()`](gstd::msg::source) must be multitoken logic contract.
+    /// - Tokens locked by vesting schedules can't be decreased.
     ///
     /// On success, replies with [`MTStorageEvent::Ok`].
     DecreaseBalance {
@@ -124,6 +298,8 @@ pub enum MTStorageAction {
         transaction_hash: H256,
         /// Identifier of the token with which decrease balance will be performed.
         token_id: TokenId,
+        /// The latest snapshot id of `token_id` at the moment the operation is created.
+        snapshot_id: u64,
         /// The actual account that made the decrease operation (initiator).
         msg_source: ActorId,
         /// An account that needs to decrease its balance.
@@ -144,4 +320,20 @@ pub enum MTStorageEvent {
     Balance(u128),
     /// Should be returned from [`MTStorageAction::GetApproval`].
     Approval(bool),
+    /// Should be returned from [`MTStorageAction::GetBalances`].
+    Balances(Vec<(TokenId, ActorId, u128)>),
+    /// Should be returned from [`MTStorageAction::HoldersOf`].
+    HoldersOf {
+        /// Requested page of holders with their balances.
+        holders: Vec<(ActorId, u128)>,
+        /// Total number of holders in this storage.
+        total: u32,
+    },
+    /// Should be returned from [`MTStorageAction::TokensOf`].
+    TokensOf {
+        /// Requested page of tokens.
+        tokens: Vec<TokenId>,
+        /// Total number of tokens held by the account.
+        total: u32,
+    },
 }
@@ -1,6 +1,14 @@
 #![no_std]
 
-use gstd::{msg, prelude::*, ActorId};
+use core::ops::Bound;
+use gstd::{
+    exec, msg,
+    prelude::{
+        collections::{BTreeMap, BTreeSet},
+        *,
+    },
+    ActorId,
+};
 use hashbrown::HashMap;
 use mt_storage_io::*;
 use primitive_types::H256;
@@ -9,20 +17,52 @@ use primitive_types::H256;
 struct MTStorage {
     mt_logic_id: ActorId,
     transaction_status: HashMap<H256, bool>,
-    balances: HashMap<TokenId, HashMap<ActorId, u128>>,
+    balances: BTreeMap<TokenId, BTreeMap<ActorId, u128>>,
     approvals: HashMap<ActorId, HashMap<ActorId, bool>>,
+    account_tokens: HashMap<ActorId, BTreeSet<TokenId>>,
+    holders_count: HashMap<TokenId, u32>,
+    current_snapshots: HashMap<TokenId, u64>,
+    snapshots: HashMap<TokenId, HashMap<ActorId, Vec<(u64, u128)>>>,
+    vestings: HashMap<TokenId, HashMap<ActorId, Vec<VestingSchedule>>>,
 }
 
 static mut MT_STORAGE: Option<MTStorage> = None;
 
 impl MTStorage {
     fn get_balance(&self, token_id: TokenId, account: &ActorId) -> u128 {
-        let token = self
-            .balances
+        self.balances
             .get(&token_id)
-            .expect("Unable to locate token.");
+            .and_then(|token| token.get(account))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Drops fully released vesting schedules and returns the number of tokens
+    /// which are still locked.
+    fn release(&mut self, token_id: TokenId, account: &ActorId) -> u128 {
+        let block_height = exec::block_height();
+        let schedules = match self
+            .vestings
+            .get_mut(&token_id)
+            .and_then(|token_vestings| token_vestings.get_mut(account))
+        {
+            Some(schedules) => schedules,
+            None => return 0,
+        };
+
+        schedules.retain(|schedule| schedule.locked(block_height) != 0);
+        let locked = schedules
+            .iter()
+            .map(|schedule| schedule.locked(block_height))
+            .sum();
 
-        *token.get(account).unwrap_or(&0)
+        if schedules.is_empty() {
+            if let Some(token_vestings) = self.vestings.get_mut(&token_id) {
+                token_vestings.remove(account);
+            }
+        }
+
+        locked
     }
 
     fn get_approval(&self, account: &ActorId, approval_target: &ActorId) -> bool {
@@ -30,12 +70,147 @@ impl MTStorage {
             return true;
         }
 
-        let account_approvals = self
-            .approvals
+        self.approvals
+            .get(account)
+            .and_then(|account_approvals| account_approvals.get(approval_target))
+            .copied()
+            .unwrap_or(false)
+    }
+
+    fn get_balances(
+        &self,
+        after: Option<(TokenId, ActorId)>,
+        limit: u32,
+    ) -> Vec<(TokenId, ActorId, u128)> {
+        let first_token = after.map_or(Bound::Unbounded, |(token_id, _)| Bound::Included(token_id));
+
+        self.balances
+            .range((first_token, Bound::Unbounded))
+            .flat_map(|(token_id, token_balances)| {
+                // Only the token of the cursor is continued from the middle
+                let first_account = match after {
+                    Some((after_token, account)) if after_token == *token_id => {
+                        Bound::Excluded(account)
+                    }
+                    _ => Bound::Unbounded,
+                };
+
+                token_balances
+                    .range((first_account, Bound::Unbounded))
+                    .map(move |(account, balance)| (*token_id, *account, *balance))
+            })
+            .take(limit as usize)
+            .collect()
+    }
+
+    fn tokens_of(&self, account: &ActorId, offset: u32, limit: u32) -> (Vec<TokenId>, u32) {
+        self.account_tokens
             .get(account)
-            .expect("Unable to locate account approvals.");
+            .map(|tokens| {
+                (
+                    tokens
+                        .iter()
+                        .skip(offset as usize)
+                        .take(limit as usize)
+                        .copied()
+                        .collect(),
+                    tokens.len() as u32,
+                )
+            })
+            .unwrap_or_default()
+    }
+
+    fn holders_of(
+        &self,
+        token_id: TokenId,
+        offset: u32,
+        limit: u32,
+    ) -> (Vec<(ActorId, u128)>, u32) {
+        let holders = self
+            .balances
+            .get(&token_id)
+            .map(|token_balances| {
+                token_balances
+                    .iter()
+                    .filter(|(_, balance)| **balance != 0)
+                    .skip(offset as usize)
+                    .take(limit as usize)
+                    .map(|(account, balance)| (*account, *balance))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        (holders, *self.holders_count.get(&token_id).unwrap_or(&0))
+    }
+
+    fn balance_of_at(
+        &self,
+        token_id: TokenId,
+        account: &ActorId,
+        snapshot_id: u64,
+    ) -> Option<u128> {
+        let current_snapshot = self.current_snapshots.get(&token_id).copied().unwrap_or(0);
+        if snapshot_id == 0 || snapshot_id > current_snapshot {
+            return None;
+        }
 
-        *account_approvals.get(approval_target).unwrap_or(&false)
+        // The first balance recorded after `snapshot_id` was taken is the balance at it,
+        // otherwise the balance hasn't changed since then
+        let recorded = self
+            .snapshots
+            .get(&token_id)
+            .and_then(|token_snapshots| token_snapshots.get(account))
+            .and_then(|account_snapshots| {
+                account_snapshots
+                    .iter()
+                    .find_map(|(id, balance)| (*id >= snapshot_id).then_some(*balance))
+            });
+
+        Some(recorded.unwrap_or_else(|| self.get_balance(token_id, account)))
+    }
+
+    fn update_snapshot(&mut self, token_id: TokenId, snapshot_id: u64, account: &ActorId) {
+        // Every balance change carries the latest snapshot id of the logic contract,
+        // so all storages switch to a new snapshot at the same point
+        if snapshot_id != 0 {
+            let current_snapshot = self.current_snapshots.entry(token_id).or_default();
+            *current_snapshot = (*current_snapshot).max(snapshot_id);
+        }
+
+        let current_snapshot = match self.current_snapshots.get(&token_id) {
+            Some(snapshot_id) => *snapshot_id,
+            None => return,
+        };
+
+        let balance = self.get_balance(token_id, account);
+        let account_snapshots = self
+            .snapshots
+            .entry(token_id)
+            .or_default()
+            .entry(*account)
+            .or_default();
+
+        if account_snapshots.last().map(|(id, _)| *id) < Some(current_snapshot) {
+            account_snapshots.push((current_snapshot, balance));
+        }
+    }
+
+    fn snapshot(&mut self, transaction_hash: H256, token_id: TokenId, snapshot_id: u64) {
+        self.assert_mt_contract();
+
+        if let Some(status) = self.transaction_status.get(&transaction_hash) {
+            match status {
+                true => reply_ok(),
+                false => reply_err(),
+            };
+            return;
+        }
+
+        let current_snapshot = self.current_snapshots.entry(token_id).or_default();
+        *current_snapshot = (*current_snapshot).max(snapshot_id);
+
+        self.transaction_status.insert(transaction_hash, true);
+        reply_ok();
     }
 
     fn assert_mt_contract(&self) {
@@ -49,10 +224,12 @@ impl MTStorage {
         self.transaction_status.remove(&transaction_hash);
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn transfer(
         &mut self,
         transaction_hash: H256,
         token_id: TokenId,
+        snapshot_id: u64,
         msg_source: &ActorId,
         sender: &ActorId,
         recipient: &ActorId,
@@ -68,20 +245,11 @@ impl MTStorage {
             return;
         }
 
-        match self.decrease(token_id, msg_source, sender, amount) {
+        match self.decrease(token_id, snapshot_id, msg_source, sender, amount) {
             true => {
-                let token_balances = self
-                    .balances
-                    .get_mut(&token_id)
-                    .expect("Unable to locate token.");
-
-                token_balances
-                    .entry(*recipient)
-                    .and_modify(|balance| {
-                        *balance = balance.checked_add(amount).expect("Math overflow.");
-                    })
-                    .or_insert_with(|| amount);
+                self.increase(token_id, snapshot_id, recipient, amount);
 
+                self.transaction_status.insert(transaction_hash, true);
                 reply_ok();
             }
             false => {
@@ -118,23 +286,37 @@ impl MTStorage {
             })
             .or_insert_with(|| [(*account, approve)].into());
 
+        self.transaction_status.insert(transaction_hash, true);
         reply_ok();
     }
 
     fn decrease(
         &mut self,
         token_id: TokenId,
+        snapshot_id: u64,
         msg_source: &ActorId,
         sender: &ActorId,
         amount: u128,
     ) -> bool {
         // Save flag before mutable borrowing
         let approved = self.get_approval(sender, msg_source);
+        let locked = self.release(token_id, sender);
+        self.update_snapshot(token_id, snapshot_id, sender);
 
         if let Some(token) = self.balances.get_mut(&token_id) {
             if let Some(balance) = token.get_mut(sender) {
-                if *balance >= amount && (msg_source == sender || approved) {
+                if balance.saturating_sub(locked) >= amount && (msg_source == sender || approved) {
                     *balance = balance.checked_sub(amount).expect("Math overflow.");
+
+                    if *balance == 0 && amount != 0 {
+                        if let Some(tokens) = self.account_tokens.get_mut(sender) {
+                            tokens.remove(&token_id);
+                        }
+
+                        let holders_count = self.holders_count.entry(token_id).or_default();
+                        *holders_count = holders_count.saturating_sub(1);
+                    }
+
                     return true;
                 }
             }
@@ -143,10 +325,33 @@ impl MTStorage {
         false
     }
 
+    fn increase(&mut self, token_id: TokenId, snapshot_id: u64, account: &ActorId, amount: u128) {
+        self.update_snapshot(token_id, snapshot_id, account);
+
+        let balance = self
+            .balances
+            .entry(token_id)
+            .or_default()
+            .entry(*account)
+            .or_default();
+        let is_new_holder = *balance == 0 && amount != 0;
+        *balance = balance.checked_add(amount).expect("Math overflow.");
+
+        if is_new_holder {
+            self.account_tokens
+                .entry(*account)
+                .or_default()
+                .insert(token_id);
+
+            *self.holders_count.entry(token_id).or_default() += 1;
+        }
+    }
+
     fn increase_balance(
         &mut self,
         transaction_hash: H256,
         token_id: TokenId,
+        snapshot_id: u64,
         account: &ActorId,
         amount: u128,
     ) {
@@ -160,21 +365,37 @@ impl MTStorage {
             return;
         }
 
-        self.balances
+        self.increase(token_id, snapshot_id, account, amount);
+
+        self.transaction_status.insert(transaction_hash, true);
+        reply_ok();
+    }
+
+    fn increase_vested_balance(
+        &mut self,
+        transaction_hash: H256,
+        token_id: TokenId,
+        snapshot_id: u64,
+        account: &ActorId,
+        schedule: VestingSchedule,
+    ) {
+        self.assert_mt_contract();
+
+        if let Some(status) = self.transaction_status.get(&transaction_hash) {
+            match status {
+                true => reply_ok(),
+                false => reply_err(),
+            };
+            return;
+        }
+
+        self.increase(token_id, snapshot_id, account, schedule.amount);
+        self.vestings
             .entry(token_id)
-            .and_modify(|token_balances| {
-                token_balances
-                    .entry(*account)
-                    .and_modify(|balance| {
-                        *balance = (*balance).checked_add(amount).expect("Math overflow.")
-                    })
-                    .or_insert(amount);
-            })
-            .or_insert_with(|| {
-                let mut token_balances = HashMap::new();
-                token_balances.insert(*account, amount);
-                token_balances
-            });
+            .or_default()
+            .entry(*account)
+            .or_default()
+            .push(schedule);
 
         self.transaction_status.insert(transaction_hash, true);
         reply_ok();
@@ -184,6 +405,7 @@ impl MTStorage {
         &mut self,
         transaction_hash: H256,
         token_id: TokenId,
+        snapshot_id: u64,
         msg_source: &ActorId,
         account: &ActorId,
         amount: u128,
@@ -198,7 +420,7 @@ impl MTStorage {
             return;
         }
 
-        match self.decrease(token_id, msg_source, account, amount) {
+        match self.decrease(token_id, snapshot_id, msg_source, account, amount) {
             true => {
                 self.transaction_status.insert(transaction_hash, true);
                 reply_ok();
@@ -234,9 +456,50 @@ extern "C" fn handle() {
             )
             .expect("Unable to reply.");
         }
+        MTStorageAction::GetBalances { after, limit } => {
+            msg::reply(
+                MTStorageEvent::Balances(storage.get_balances(after, limit)),
+                0,
+            )
+            .expect("Unable to reply.");
+        }
+        MTStorageAction::TokensOf {
+            account,
+            offset,
+            limit,
+        } => {
+            let (tokens, total) = storage.tokens_of(&account, offset, limit);
+            msg::reply(MTStorageEvent::TokensOf { tokens, total }, 0).expect("Unable to reply.");
+        }
+        MTStorageAction::HoldersOf {
+            token_id,
+            offset,
+            limit,
+        } => {
+            let (holders, total) = storage.holders_of(token_id, offset, limit);
+            msg::reply(MTStorageEvent::HoldersOf { holders, total }, 0).expect("Unable to reply.");
+        }
+        MTStorageAction::BalanceOfAt {
+            token_id,
+            account,
+            snapshot_id,
+        } => match storage.balance_of_at(token_id, &account, snapshot_id) {
+            Some(balance) => {
+                msg::reply(MTStorageEvent::Balance(balance), 0).expect("Unable to reply.");
+            }
+            None => reply_err(),
+        },
+        MTStorageAction::Snapshot {
+            transaction_hash,
+            token_id,
+            snapshot_id,
+        } => {
+            storage.snapshot(transaction_hash, token_id, snapshot_id);
+        }
         MTStorageAction::Transfer {
             transaction_hash,
             token_id,
+            snapshot_id,
             msg_source,
             sender,
             recipient,
@@ -245,6 +508,7 @@ extern "C" fn handle() {
             storage.transfer(
                 transaction_hash,
                 token_id,
+                snapshot_id,
                 &msg_source,
                 &sender,
                 &recipient,
@@ -265,27 +529,53 @@ extern "C" fn handle() {
         MTStorageAction::IncreaseBalance {
             transaction_hash,
             token_id,
+            snapshot_id,
             account,
             amount,
         } => {
-            storage.increase_balance(transaction_hash, token_id, &account, amount);
+            storage.increase_balance(transaction_hash, token_id, snapshot_id, &account, amount);
+        }
+        MTStorageAction::IncreaseVestedBalance {
+            transaction_hash,
+            token_id,
+            snapshot_id,
+            account,
+            schedule,
+        } => {
+            storage.increase_vested_balance(
+                transaction_hash,
+                token_id,
+                snapshot_id,
+                &account,
+                schedule,
+            );
         }
         MTStorageAction::DecreaseBalance {
             transaction_hash,
             token_id,
+            snapshot_id,
             msg_source,
             account,
             amount,
         } => {
-            storage.decrease_balance(transaction_hash, token_id, &msg_source, &account, amount);
+            storage.decrease_balance(
+                transaction_hash,
+                token_id,
+                snapshot_id,
+                &msg_source,
+                &account,
+                amount,
+            );
         }
     }
 }
 
 #[no_mangle]
 extern "C" fn init() {
+    let init_config: InitMTStorage = msg::load().expect("Unable to decode `InitMTStorage`.");
     let storage = MTStorage {
         mt_logic_id: msg::source(),
+        current_snapshots: init_config.snapshots.into_iter().collect(),
         ..Default::default()
     };
     unsafe { MT_STORAGE = Some(storage) };
@@ -316,6 +606,31 @@ extern "C" fn state() {
                 )
             })
             .collect(),
+        account_tokens: storage
+            .account_tokens
+            .iter()
+            .map(|(key, value)| (*key, value.iter().copied().collect()))
+            .collect(),
+        holders_count: storage
+            .holders_count
+            .iter()
+            .map(|(key, value)| (*key, *value))
+            .collect(),
+        current_snapshots: storage
+            .current_snapshots
+            .iter()
+            .map(|(key, value)| (*key, *value))
+            .collect(),
+        snapshots: storage
+            .snapshots
+            .iter()
+            .map(|(key, value)| (*key, value.iter().map(|(a, b)| (*a, b.clone())).collect()))
+            .collect(),
+        vestings: storage
+            .vestings
+            .iter()
+            .map(|(key, value)| (*key, value.iter().map(|(a, b)| (*a, b.clone())).collect()))
+            .collect(),
     };
 
     msg::reply(storage_state, 0).expect("Failed to share state.");
@@ -0,0 +1,161 @@
+#![no_std]
+
+use gmeta::{metawasm, Metadata};
+use gstd::{prelude::*, ActorId};
+use mt_storage_io::*;
+use primitive_types::H256;
+
+#[metawasm]
+pub mod metafns {
+    pub type State = <MTStorageMetadata as Metadata>::State;
+
+    /// Returns the status of the transaction with `transaction_hash`.
+    pub fn transaction_status(state: State, transaction_hash: H256) -> Option<bool> {
+        state
+            .transaction_status
+            .into_iter()
+            .find_map(|(hash, status)| (hash == transaction_hash).then_some(status))
+    }
+
+    /// Returns `account` balance of `token_id` tokens.
+    pub fn balance_of(state: State, token_id: TokenId, account: ActorId) -> u128 {
+        state
+            .balances
+            .into_iter()
+            .find_map(|(id, balances)| (id == token_id).then_some(balances))
+            .and_then(|balances| {
+                balances
+                    .into_iter()
+                    .find_map(|(holder, balance)| (holder == account).then_some(balance))
+            })
+            .unwrap_or_default()
+    }
+
+    /// Returns a page of balances ordered by token id and account.
+    pub fn balances(
+        state: State,
+        after: Option<(TokenId, ActorId)>,
+        limit: u32,
+    ) -> Vec<(TokenId, ActorId, u128)> {
+        let balances = state
+            .balances
+            .into_iter()
+            .flat_map(|(token_id, balances)| {
+                balances
+                    .into_iter()
+                    .map(move |(account, balance)| ((token_id, account), balance))
+            })
+            .collect();
+
+        paginate_sorted(balances, after, limit)
+            .into_iter()
+            .map(|((token_id, account), balance)| (token_id, account, balance))
+            .collect()
+    }
+
+    /// Returns all accounts approved by `account`.
+    pub fn approvals_of(state: State, account: ActorId) -> Vec<ActorId> {
+        let mut approvals: Vec<ActorId> = state
+            .approvals
+            .into_iter()
+            .find_map(|(owner, approvals)| (owner == account).then_some(approvals))
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(target, approved)| approved.then_some(target))
+            .collect();
+        approvals.sort_unstable();
+        approvals
+    }
+
+    /// Returns all tokens with non-zero `account` balance.
+    pub fn tokens_of(state: State, account: ActorId) -> Vec<TokenId> {
+        state
+            .account_tokens
+            .into_iter()
+            .find_map(|(holder, tokens)| (holder == account).then_some(tokens))
+            .unwrap_or_default()
+    }
+
+    /// Returns the number of accounts with non-zero `token_id` balance.
+    pub fn holders_count(state: State, token_id: TokenId) -> u32 {
+        state
+            .holders_count
+            .into_iter()
+            .find_map(|(id, count)| (id == token_id).then_some(count))
+            .unwrap_or_default()
+    }
+
+    /// Returns `account` balance of `token_id` tokens at the moment when `snapshot_id` was taken.
+    pub fn balance_of_at(
+        state: State,
+        token_id: TokenId,
+        account: ActorId,
+        snapshot_id: u64,
+    ) -> Option<u128> {
+        let current_snapshot = state
+            .current_snapshots
+            .iter()
+            .find_map(|(id, snapshot_id)| (*id == token_id).then_some(*snapshot_id))
+            .unwrap_or_default();
+        if snapshot_id == 0 || snapshot_id > current_snapshot {
+            return None;
+        }
+
+        let recorded = state
+            .snapshots
+            .iter()
+            .find_map(|(id, snapshots)| (*id == token_id).then_some(snapshots))
+            .and_then(|snapshots| {
+                snapshots
+                    .iter()
+                    .find_map(|(holder, snapshots)| (*holder == account).then_some(snapshots))
+            })
+            .and_then(|snapshots| {
+                snapshots
+                    .iter()
+                    .find_map(|(id, balance)| (*id >= snapshot_id).then_some(*balance))
+            });
+
+        if recorded.is_some() {
+            return recorded;
+        }
+
+        let balance = state
+            .balances
+            .into_iter()
+            .find_map(|(id, balances)| (id == token_id).then_some(balances))
+            .and_then(|balances| {
+                balances
+                    .into_iter()
+                    .find_map(|(holder, balance)| (holder == account).then_some(balance))
+            })
+            .unwrap_or_default();
+
+        Some(balance)
+    }
+
+    /// Returns the number of `account` tokens locked by vesting schedules at `block_height`.
+    pub fn locked_balance(
+        state: State,
+        token_id: TokenId,
+        account: ActorId,
+        block_height: u32,
+    ) -> u128 {
+        state
+            .vestings
+            .into_iter()
+            .find_map(|(id, vestings)| (id == token_id).then_some(vestings))
+            .and_then(|vestings| {
+                vestings
+                    .into_iter()
+                    .find_map(|(holder, schedules)| (holder == account).then_some(schedules))
+            })
+            .map(|schedules| {
+                schedules
+                    .iter()
+                    .map(|schedule| schedule.locked(block_height))
+                    .sum()
+            })
+            .unwrap_or_default()
+    }
+}